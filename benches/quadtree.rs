@@ -0,0 +1,63 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use roguemetry::{Distance, Point, Quadtree, TileRect};
+
+const WORLD_SIZE: i32 = 4096;
+const ENTITIES: i32 = 10_000;
+
+/// Entities scattered over a big sparse world, deterministic without `rand`
+fn entities() -> Vec<Point> {
+    (0..ENTITIES)
+        .map(|i| {
+            Point::new(
+                (i * 7919) % WORLD_SIZE,
+                (i * 104_729 / 7 + i * 31) % WORLD_SIZE,
+            )
+        })
+        .collect()
+}
+
+fn range_query(c: &mut Criterion) {
+    let points = entities();
+    let mut tree = Quadtree::new(TileRect::with_size(WORLD_SIZE, WORLD_SIZE));
+    for (i, &point) in points.iter().enumerate() {
+        tree.insert(point, i);
+    }
+    let area = TileRect::new(1000, 1000, 64, 64);
+
+    let mut group = c.benchmark_group("range_query");
+    group.bench_function("quadtree", |b| b.iter(|| tree.query(black_box(area)).len()));
+    group.bench_function("brute_force", |b| {
+        b.iter(|| {
+            points
+                .iter()
+                .filter(|&&point| black_box(area).contains(point))
+                .count()
+        });
+    });
+    group.finish();
+}
+
+fn nearest(c: &mut Criterion) {
+    let points = entities();
+    let mut tree = Quadtree::new(TileRect::with_size(WORLD_SIZE, WORLD_SIZE));
+    for (i, &point) in points.iter().enumerate() {
+        tree.insert(point, i);
+    }
+    let target = Point::new(2048, 1024);
+
+    let mut group = c.benchmark_group("nearest");
+    group.bench_function("quadtree", |b| {
+        b.iter(|| tree.nearest(black_box(target), Distance::Euclidean));
+    });
+    group.bench_function("brute_force", |b| {
+        b.iter(|| {
+            points
+                .iter()
+                .min_by_key(|&&point| point.square_distance_to(black_box(target)))
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, range_query, nearest);
+criterion_main!(benches);
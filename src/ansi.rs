@@ -0,0 +1,398 @@
+//! ANSI art (`.ans`): CP437 text with escape sequences for cursor movement and colours,
+//! as drawn for BBS and DOS screens.
+//!
+//! Colours are 16 indices in ANSI order: black, red, green, yellow, blue, magenta, cyan, white,
+//! then the same eight bright colours.
+
+use super::{Grid, Point};
+
+/// Ends the art, a SAUCE metadata record may follow
+const SUB: u8 = 0x1A;
+const ESC: u8 = 0x1B;
+
+/// Glyphs that can't be written since the parser reads them as line breaks, end of file
+/// or an escape sequence: '◙', '♪', '→' and '←' in CP437
+pub const RESERVED: [u8; 4] = [b'\n', b'\r', SUB, ESC];
+
+/// Widest screen [`Ansi::parse`] draws on, wider settings are limited to it
+pub const MAX_WIDTH: i32 = 1000;
+
+/// Rows [`Ansi::parse`] keeps at most, the cursor can't move below the last one
+pub const MAX_HEIGHT: i32 = 10_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnsiCell {
+    /// CP437 byte
+    pub glyph: u8,
+    /// Foreground colour, `0..16`
+    pub fg: u8,
+    /// Background colour, `0..8` or `0..16` with iCE colours
+    pub bg: u8,
+}
+
+impl AnsiCell {
+    #[must_use]
+    pub const fn new(glyph: u8, fg: u8, bg: u8) -> Self {
+        AnsiCell { glyph, fg, bg }
+    }
+}
+
+impl Default for AnsiCell {
+    /// Light grey space on black
+    fn default() -> Self {
+        AnsiCell::new(b' ', 7, 0)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Settings for reading and writing ANSI art
+pub struct Ansi {
+    /// Columns of the screen, text wraps after the last one
+    pub width: i32,
+    /// Blink attribute selects bright backgrounds instead of blinking text
+    pub ice_colors: bool,
+}
+
+impl Default for Ansi {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            ice_colors: false,
+        }
+    }
+}
+
+impl Ansi {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_width(mut self, width: i32) -> Self {
+        self.width = width.clamp(1, MAX_WIDTH);
+        self
+    }
+
+    #[must_use]
+    pub fn with_ice_colors(mut self, ice_colors: bool) -> Self {
+        self.ice_colors = ice_colors;
+        self
+    }
+
+    #[must_use]
+    /// Draw the art on an empty screen, the grid is as tall as the lowest row written to.
+    /// Unknown escape sequences are skipped.
+    /// The width is limited to `1..=MAX_WIDTH` and the height to [`MAX_HEIGHT`]
+    /// and to the length of the data, so a few cursor movements can't make a huge grid.
+    pub fn parse(self, data: &[u8]) -> Grid<AnsiCell> {
+        let height = i32::try_from(data.len())
+            .unwrap_or(i32::MAX)
+            .clamp(1, MAX_HEIGHT);
+        let mut screen = Screen::new(self, height);
+        let mut bytes = data.iter().copied().take_while(|&b| b != SUB).peekable();
+        while let Some(byte) = bytes.next() {
+            match byte {
+                ESC if bytes.peek() == Some(&b'[') => {
+                    bytes.next();
+                    let mut params = Vec::new();
+                    let mut current: Option<i32> = None;
+                    let command = loop {
+                        match bytes.next() {
+                            Some(digit @ b'0'..=b'9') => {
+                                let value = current.unwrap_or(0);
+                                current = Some(
+                                    value
+                                        .saturating_mul(10)
+                                        .saturating_add(i32::from(digit - b'0')),
+                                );
+                            }
+                            Some(b';') => params.push(current.take()),
+                            // Private markers like in "ESC[?7h"
+                            Some(b'?' | b'=' | b'>') => {}
+                            other => break other,
+                        }
+                    };
+                    params.push(current);
+                    match command {
+                        Some(command) => screen.command(command, &params),
+                        None => break,
+                    }
+                }
+                b'\r' => screen.cursor.x = 0,
+                b'\n' => screen.new_line(),
+                glyph => screen.put(glyph),
+            }
+        }
+        screen.into_grid()
+    }
+
+    /// Write the grid as ANSI art, every row ends with CR LF and colours are reset at the end
+    ///
+    /// # Errors
+    ///
+    /// Returns the first cell with a [`RESERVED`] glyph, row by row
+    pub fn write(self, grid: &Grid<AnsiCell>) -> Result<Vec<u8>, Point> {
+        if let Some((point, _)) = grid.iter().find(|(_, cell)| RESERVED.contains(&cell.glyph)) {
+            return Err(point);
+        }
+        let mut data = Vec::new();
+        let mut attributes = None;
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let cell = grid[Point::new(x, y)];
+                let bg = if self.ice_colors {
+                    cell.bg
+                } else {
+                    cell.bg & 7
+                };
+                if attributes != Some((cell.fg, bg)) {
+                    attributes = Some((cell.fg, bg));
+                    data.extend(b"\x1b[0");
+                    if cell.fg >= 8 {
+                        data.extend(b";1");
+                    }
+                    if bg >= 8 {
+                        data.extend(b";5");
+                    }
+                    data.extend(format!(";{};{}m", 30 + (cell.fg & 7), 40 + (bg & 7)).bytes());
+                }
+                data.push(cell.glyph);
+            }
+            data.extend(b"\r\n");
+        }
+        data.extend(b"\x1b[0m");
+        Ok(data)
+    }
+}
+
+/// Virtual terminal the art is drawn on
+struct Screen {
+    settings: Ansi,
+    /// Row the cursor can't move below
+    last_row: i32,
+    /// Rows written to, each only as long as its last written cell
+    rows: Vec<Vec<AnsiCell>>,
+    cursor: Point,
+    saved: Point,
+    pen: AnsiCell,
+    bold: bool,
+    blink: bool,
+}
+
+impl Screen {
+    fn new(settings: Ansi, height: i32) -> Self {
+        Screen {
+            settings: settings.with_width(settings.width),
+            last_row: height - 1,
+            rows: Vec::new(),
+            cursor: Point::default(),
+            saved: Point::default(),
+            pen: AnsiCell::default(),
+            bold: false,
+            blink: false,
+        }
+    }
+
+    /// Row at `y`, grown to `length` cells
+    fn row(&mut self, y: i32, length: usize) -> &mut Vec<AnsiCell> {
+        let y = usize::try_from(y).unwrap_or(0);
+        if self.rows.len() <= y {
+            self.rows.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.rows[y];
+        if row.len() < length {
+            row.resize(length, AnsiCell::default());
+        }
+        row
+    }
+
+    fn new_line(&mut self) {
+        self.cursor = Point::new(0, (self.cursor.y + 1).min(self.last_row));
+    }
+
+    fn put(&mut self, glyph: u8) {
+        if self.cursor.x >= self.settings.width {
+            self.new_line();
+        }
+        let cell = AnsiCell { glyph, ..self.pen };
+        let x = usize::try_from(self.cursor.x).unwrap_or(0);
+        self.row(self.cursor.y, x + 1)[x] = cell;
+        self.cursor.x += 1;
+    }
+
+    fn command(&mut self, command: u8, params: &[Option<i32>]) {
+        let count = params.first().copied().flatten().unwrap_or(1).max(1);
+        let last_column = self.settings.width - 1;
+        match command {
+            b'A' => self.cursor.y = self.cursor.y.saturating_sub(count).max(0),
+            b'B' => self.cursor.y = self.cursor.y.saturating_add(count).min(self.last_row),
+            b'C' => self.cursor.x = self.cursor.x.saturating_add(count).min(last_column),
+            b'D' => self.cursor.x = self.cursor.x.min(last_column).saturating_sub(count).max(0),
+            b'H' | b'f' => {
+                let row = params.first().copied().flatten().unwrap_or(1).max(1);
+                let column = params.get(1).copied().flatten().unwrap_or(1).max(1);
+                self.cursor =
+                    Point::new((column - 1).min(last_column), (row - 1).min(self.last_row));
+            }
+            b'J' if params.first().copied().flatten() == Some(2) => {
+                self.rows.clear();
+                self.cursor = Point::default();
+            }
+            b'K' => {
+                let (x, pen) = (self.cursor.x, self.pen);
+                let width = usize::try_from(self.settings.width).unwrap_or(0);
+                for cell in self
+                    .row(self.cursor.y, width)
+                    .iter_mut()
+                    .skip(x.try_into().unwrap_or(0))
+                {
+                    *cell = AnsiCell { glyph: b' ', ..pen };
+                }
+            }
+            b's' => self.saved = self.cursor,
+            b'u' => self.cursor = self.saved,
+            b'm' => {
+                for &param in params {
+                    self.select_graphic_rendition(param.unwrap_or(0));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn select_graphic_rendition(&mut self, param: i32) {
+        match param {
+            0 => {
+                self.pen = AnsiCell::default();
+                self.bold = false;
+                self.blink = false;
+            }
+            1 => self.bold = true,
+            5 => self.blink = true,
+            22 => self.bold = false,
+            25 => self.blink = false,
+            30..=37 => self.pen.fg = (param - 30) as u8,
+            39 => self.pen.fg = 7,
+            40..=47 => self.pen.bg = (param - 40) as u8,
+            49 => self.pen.bg = 0,
+            90..=97 => self.pen.fg = (param - 90) as u8 | 8,
+            100..=107 => self.pen.bg = (param - 100) as u8 | 8,
+            _ => {}
+        }
+        if matches!(param, 0 | 1 | 22 | 30..=37 | 39) {
+            self.pen.fg = self.pen.fg & 7 | if self.bold { 8 } else { 0 };
+        }
+        if matches!(param, 0 | 5 | 25 | 40..=47 | 49) {
+            let bright = self.blink && self.settings.ice_colors;
+            self.pen.bg = self.pen.bg & 7 | if bright { 8 } else { 0 };
+        }
+    }
+
+    fn into_grid(self) -> Grid<AnsiCell> {
+        let height = i32::try_from(self.rows.len()).unwrap_or(i32::MAX);
+        Grid::from_fn(self.settings.width, height, |point| {
+            self.rows[usize::try_from(point.y).unwrap_or(0)]
+                .get(usize::try_from(point.x).unwrap_or(0))
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ansi, AnsiCell, Grid, Point, MAX_HEIGHT, RESERVED};
+
+    #[test]
+    fn cursor_and_colours() {
+        let art =
+            b"\x1b[1;31mA\x1b[0m\x1b[3;5HB\x1b[2AC\r\n\x1b[44;5mD\x1b[sE\x1b[u\x1b[32mF\x1aignored";
+        let grid = Ansi::new().with_width(10).parse(art);
+        assert_eq!((10, 3), (grid.width(), grid.height()));
+        assert_eq!(AnsiCell::new(b'A', 9, 0), grid[Point::new(0, 0)]);
+        assert_eq!(b'B', grid[Point::new(4, 2)].glyph);
+        assert_eq!(b'C', grid[Point::new(5, 0)].glyph);
+        // Blink without iCE colours keeps the background dark
+        assert_eq!(AnsiCell::new(b'D', 7, 4), grid[Point::new(0, 1)]);
+        assert_eq!(AnsiCell::new(b'F', 2, 4), grid[Point::new(1, 1)]);
+
+        let ice = Ansi::new().with_ice_colors(true).parse(b"\x1b[5;44mX");
+        assert_eq!(AnsiCell::new(b'X', 7, 12), ice[Point::new(0, 0)]);
+    }
+
+    #[test]
+    fn wrapping() {
+        let grid = Ansi::new().with_width(3).parse(b"abcd\r\nef");
+        assert_eq!(3, grid.height());
+        assert_eq!(b'd', grid[Point::new(0, 1)].glyph);
+        assert_eq!(b'e', grid[Point::new(0, 2)].glyph);
+        // Line feed right after a full line doesn't leave an empty row
+        assert_eq!(2, Ansi::new().with_width(3).parse(b"abc\r\nd").height());
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let grid = Grid::from_fn(4, 2, |p| {
+            let colour = u8::try_from(p.x + p.y * 4).unwrap();
+            AnsiCell::new(b'a' + colour, colour * 2 % 16, 15 - colour)
+        });
+        let ansi = Ansi::new().with_width(4).with_ice_colors(true);
+        assert_eq!(
+            Ok(grid.clone()),
+            ansi.write(&grid).map(|data| ansi.parse(&data))
+        );
+
+        let plain = Ansi::new().with_width(4);
+        let dark = plain.parse(&plain.write(&grid).unwrap());
+        assert_eq!(
+            grid.map(|_, cell| AnsiCell {
+                bg: cell.bg & 7,
+                ..*cell
+            }),
+            dark
+        );
+
+        // Every byte except the reserved ones survives, e.g. '♥' (3) and '•' (7)
+        let glyphs: Vec<u8> = (0..=255).filter(|b| !RESERVED.contains(b)).collect();
+        let ansi = Ansi::new().with_width(16);
+        let grid = Grid::from_fn(16, 16, |p| {
+            let glyph = glyphs.get(usize::try_from(p.x + p.y * 16).unwrap());
+            AnsiCell::new(glyph.copied().unwrap_or(b' '), 7, 0)
+        });
+        assert_eq!(grid, ansi.parse(&ansi.write(&grid).unwrap()));
+
+        for glyph in RESERVED {
+            let mut grid = Grid::new(3, 2, AnsiCell::default());
+            grid[Point::new(2, 1)].glyph = glyph;
+            assert_eq!(Err(Point::new(2, 1)), ansi.write(&grid));
+        }
+    }
+
+    #[test]
+    fn hostile_input() {
+        let ansi = Ansi::new().with_width(10);
+        let grid = ansi.parse(b"\x1b[99999999999A\x1b[99999999999Cx");
+        assert_eq!(b'x', grid[Point::new(9, 0)].glyph);
+        // The cursor can't move below a row for every byte of the data
+        let data = b"\x1b[2147483647BX\x1b[2147483647;2147483647HY";
+        let grid = ansi.parse(data);
+        let last = i32::try_from(data.len()).unwrap() - 1;
+        assert_eq!(last + 1, grid.height());
+        assert_eq!(b'Y', grid[Point::new(9, last)].glyph);
+        let long = [b"\x1b[9B".as_slice(); 5000].concat();
+        assert_eq!(
+            MAX_HEIGHT,
+            ansi.parse(&[long.as_slice(), b"Z"].concat()).height()
+        );
+
+        let zero = Ansi {
+            width: 0,
+            ice_colors: false,
+        };
+        assert_eq!(1, zero.parse(b"ab").width());
+        assert_eq!(1000, Ansi::new().with_width(i32::MAX).width);
+    }
+}
@@ -0,0 +1,145 @@
+//! Neighbour bitmasks for picking wall and terrain sprites.
+//!
+//! Bits follow `DIR8` order: bit 0 is east, bit 1 south-east and so on clockwise up to north-east.
+//! 4-bit masks only use the cardinal directions of that order, bit `i` is `DIR4[i]`:
+//! east, south, west, north.
+
+use super::{cp437, Point, DIR4, DIR8};
+
+pub const EAST: u8 = 1;
+pub const SOUTH: u8 = 2;
+pub const WEST: u8 = 4;
+pub const NORTH: u8 = 8;
+
+/// Number of distinct reduced 8-bit masks
+pub const BLOB_COUNT: usize = 47;
+
+/// All reduced 8-bit masks in ascending order, position in this array is the blob index
+pub const BLOB_MASKS: [u8; BLOB_COUNT] = blob_tables().0;
+
+/// Blob index of every 8-bit mask
+const BLOB_INDICES: [u8; 256] = blob_tables().1;
+
+#[must_use]
+/// 4-bit mask of cardinal neighbours for which `same` returns true
+pub fn mask4<F>(point: Point, mut same: F) -> u8
+where
+    F: FnMut(Point) -> bool,
+{
+    (0..)
+        .zip(DIR4)
+        .filter(|&(_, dir)| same(point + dir))
+        .fold(0, |mask, (bit, _)| mask | 1 << bit)
+}
+
+#[must_use]
+/// 8-bit mask of all neighbours for which `same` returns true
+pub fn mask8<F>(point: Point, mut same: F) -> u8
+where
+    F: FnMut(Point) -> bool,
+{
+    (0..)
+        .zip(DIR8)
+        .filter(|&(_, dir)| same(point + dir))
+        .fold(0, |mask, (bit, _)| mask | 1 << bit)
+}
+
+#[inline]
+#[must_use]
+/// 4-bit mask made of the cardinal bits of an 8-bit mask
+pub const fn to_mask4(mask8: u8) -> u8 {
+    (mask8 & 1) | (mask8 >> 1 & 2) | (mask8 >> 2 & 4) | (mask8 >> 3 & 8)
+}
+
+#[inline]
+#[must_use]
+/// Clear diagonal bits whose both adjacent cardinal neighbours aren't set.
+/// Such corners don't change how the tile looks, which leaves 47 distinct masks.
+pub const fn reduce(mask8: u8) -> u8 {
+    let mut reduced = mask8 & 0b0101_0101;
+    let mut diagonal = 1;
+    while diagonal < 8 {
+        let sides = 1 << (diagonal - 1) | 1 << ((diagonal + 1) % 8);
+        if mask8 & sides == sides {
+            reduced |= mask8 & 1 << diagonal;
+        }
+        diagonal += 2;
+    }
+    reduced
+}
+
+#[inline]
+#[must_use]
+/// Index of the mask in the 47 tile blob set, see [`BLOB_MASKS`]
+pub fn blob_index(mask8: u8) -> usize {
+    usize::from(BLOB_INDICES[usize::from(mask8)])
+}
+
+#[must_use]
+/// CP437 single line box-drawing byte connecting the sides in a 4-bit mask.
+/// A tile without neighbours is drawn as a pillar.
+pub fn box_byte(mask4: u8) -> u8 {
+    cp437::box_drawing(|dir| {
+        let bit = DIR4.iter().position(|&cardinal| cardinal == dir)?;
+        (mask4 & 1 << bit != 0).then_some(cp437::Line::Single)
+    })
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn blob_tables() -> ([u8; BLOB_COUNT], [u8; 256]) {
+    let mut masks = [0; BLOB_COUNT];
+    let mut indices = [0; 256];
+    let mut count = 0;
+    let mut mask = 0;
+    while mask < 256 {
+        let reduced = reduce(mask as u8);
+        if reduced as usize == mask {
+            masks[count] = reduced;
+            indices[mask] = count as u8;
+            count += 1;
+        } else {
+            // Reducing only clears bits, so the reduced mask was already numbered
+            indices[mask] = indices[reduced as usize];
+        }
+        mask += 1;
+    }
+    (masks, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blob_index, box_byte, mask4, mask8, reduce, to_mask4, BLOB_MASKS};
+    use crate::Point;
+
+    #[test]
+    fn masks() {
+        let walls = [(1, 0), (1, 1), (0, -1), (-1, -1)].map(Point::from);
+        let same = |p: Point| walls.contains(&p);
+        let mask = mask8(Point::new(0, 0), same);
+        assert_eq!(0b0110_0011, mask);
+        assert_eq!(0b1001, mask4(Point::new(0, 0), same));
+        assert_eq!(0b1001, to_mask4(mask));
+        // North-west corner isn't next to a western neighbour
+        assert_eq!(0b0100_0001, reduce(mask));
+    }
+
+    #[test]
+    fn blob() {
+        assert_eq!(0, BLOB_MASKS[0]);
+        assert_eq!(255, BLOB_MASKS[46]);
+        assert!(BLOB_MASKS.windows(2).all(|pair| pair[0] < pair[1]));
+        for mask in 0..=255 {
+            assert_eq!(BLOB_MASKS[blob_index(mask)], reduce(mask));
+        }
+        assert_eq!(blob_index(0b0000_0001), blob_index(0b1010_0011));
+    }
+
+    #[test]
+    fn box_drawing() {
+        assert_eq!(197, box_byte(0b1111));
+        assert_eq!(218, box_byte(0b0011));
+        assert_eq!(196, box_byte(0b0001));
+        assert_eq!(179, box_byte(0b1010));
+        assert_eq!(254, box_byte(0));
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use super::{Grid, Point, TileRect};
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Position of a square chunk of tiles, measured in chunks.
+pub struct ChunkCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkCoord {
+    #[must_use]
+    pub const fn new(x: i32, y: i32) -> Self {
+        ChunkCoord { x, y }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Split a world point into its chunk and the position inside that chunk.
+    /// Negative coordinates are floored, so `-1` is the last tile of chunk `-1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` isn't positive.
+    pub fn split(point: Point, chunk_size: i32) -> (ChunkCoord, Point) {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        (
+            ChunkCoord::new(
+                point.x.div_euclid(chunk_size),
+                point.y.div_euclid(chunk_size),
+            ),
+            Point::new(
+                point.x.rem_euclid(chunk_size),
+                point.y.rem_euclid(chunk_size),
+            ),
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    /// Chunk containing the world point
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` isn't positive.
+    pub fn of(point: Point, chunk_size: i32) -> Self {
+        Self::split(point, chunk_size).0
+    }
+
+    #[inline]
+    #[must_use]
+    /// World position of the top-left tile of the chunk
+    pub fn origin(self, chunk_size: i32) -> Point {
+        Point::new(self.x * chunk_size, self.y * chunk_size)
+    }
+
+    #[inline]
+    #[must_use]
+    /// World position of a tile inside the chunk
+    pub fn to_world(self, local: Point, chunk_size: i32) -> Point {
+        self.origin(chunk_size) + local
+    }
+
+    #[inline]
+    #[must_use]
+    /// World tiles covered by the chunk
+    pub fn rect(self, chunk_size: i32) -> TileRect {
+        let origin = self.origin(chunk_size);
+        TileRect::new(origin.x, origin.y, chunk_size, chunk_size)
+    }
+}
+
+impl From<(i32, i32)> for ChunkCoord {
+    fn from((x, y): (i32, i32)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<ChunkCoord> for (i32, i32) {
+    fn from(coord: ChunkCoord) -> Self {
+        (coord.x, coord.y)
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Unbounded grid split into square chunks, which are allocated on first write.
+/// Tiles of chunks that were never written read as the fill value.
+pub struct ChunkedGrid<T> {
+    chunk_size: i32,
+    fill: T,
+    chunks: HashMap<ChunkCoord, Grid<T>>,
+}
+
+impl<T: Clone> ChunkedGrid<T> {
+    #[must_use]
+    /// Create an empty world of `chunk_size` x `chunk_size` chunks filled with `fill`
+    pub fn new(chunk_size: i32, fill: T) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            fill,
+            chunks: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn chunk_size(&self) -> i32 {
+        self.chunk_size
+    }
+
+    #[inline]
+    #[must_use]
+    /// Value of tiles in chunks that aren't loaded
+    pub fn fill(&self) -> &T {
+        &self.fill
+    }
+
+    #[must_use]
+    /// Tile at the world point, fill value if its chunk isn't loaded
+    pub fn get(&self, point: Point) -> &T {
+        let (coord, local) = ChunkCoord::split(point, self.chunk_size);
+        self.chunks
+            .get(&coord)
+            .map_or(&self.fill, |chunk| &chunk[local])
+    }
+
+    /// Mutable tile at the world point, loading its chunk if needed
+    pub fn get_mut(&mut self, point: Point) -> &mut T {
+        let (coord, local) = ChunkCoord::split(point, self.chunk_size);
+        &mut self.load(coord)[local]
+    }
+
+    /// Replace the tile at the world point, returning the old value
+    pub fn set(&mut self, point: Point, value: T) -> T {
+        std::mem::replace(self.get_mut(point), value)
+    }
+
+    /// Chunk at the coordinate, allocated and filled if it wasn't loaded
+    pub fn load(&mut self, coord: ChunkCoord) -> &mut Grid<T> {
+        let size = self.chunk_size;
+        let fill = &self.fill;
+        self.chunks
+            .entry(coord)
+            .or_insert_with(|| Grid::new(size, size, fill.clone()))
+    }
+
+    /// Drop a chunk from memory, returning its tiles
+    pub fn unload(&mut self, coord: ChunkCoord) -> Option<Grid<T>> {
+        self.chunks.remove(&coord)
+    }
+
+    /// Put previously saved tiles back, returns false if their size doesn't match the chunk size
+    pub fn insert_chunk(&mut self, coord: ChunkCoord, chunk: Grid<T>) -> bool {
+        if chunk.width() != self.chunk_size || chunk.height() != self.chunk_size {
+            return false;
+        }
+        self.chunks.insert(coord, chunk);
+        true
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_loaded(&self, coord: ChunkCoord) -> bool {
+        self.chunks.contains_key(&coord)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn chunk(&self, coord: ChunkCoord) -> Option<&Grid<T>> {
+        self.chunks.get(&coord)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn chunk_mut(&mut self, coord: ChunkCoord) -> Option<&mut Grid<T>> {
+        self.chunks.get_mut(&coord)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Number of loaded chunks
+    pub fn loaded_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Iterate over loaded chunks in no particular order
+    pub fn chunks(&self) -> impl Iterator<Item = (ChunkCoord, &Grid<T>)> {
+        self.chunks.iter().map(|(&coord, chunk)| (coord, chunk))
+    }
+
+    /// Iterate mutably over loaded chunks in no particular order
+    pub fn chunks_mut(&mut self) -> impl Iterator<Item = (ChunkCoord, &mut Grid<T>)> {
+        self.chunks.iter_mut().map(|(&coord, chunk)| (coord, chunk))
+    }
+
+    /// Iterate over tiles of loaded chunks with their world points
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        let size = self.chunk_size;
+        self.chunks().flat_map(move |(coord, chunk)| {
+            chunk
+                .iter()
+                .map(move |(local, value)| (coord.to_world(local, size), value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkCoord, ChunkedGrid, Point};
+
+    #[test]
+    #[should_panic(expected = "chunk size must be positive")]
+    fn zero_chunk_size() {
+        let _ = ChunkCoord::of(Point::new(1, 1), 0);
+    }
+
+    #[test]
+    fn split_negative() {
+        assert_eq!(
+            (ChunkCoord::new(-1, 0), Point::new(15, 3)),
+            ChunkCoord::split(Point::new(-1, 3), 16)
+        );
+        assert_eq!(
+            (ChunkCoord::new(-2, -1), Point::new(0, 0)),
+            ChunkCoord::split(Point::new(-32, -16), 16)
+        );
+        for point in [Point::new(-17, 40), Point::new(0, -1), Point::new(31, 32)] {
+            let (coord, local) = ChunkCoord::split(point, 16);
+            assert_eq!(point, coord.to_world(local, 16));
+            assert!(coord.rect(16).contains(point));
+        }
+    }
+
+    #[test]
+    fn lazy_chunks() {
+        let mut world = ChunkedGrid::new(8, '.');
+        assert_eq!(&'.', world.get(Point::new(-100, 5)));
+        assert_eq!(0, world.loaded_count());
+
+        assert_eq!('.', world.set(Point::new(-1, -1), '#'));
+        world.set(Point::new(3, 3), '@');
+        assert_eq!(2, world.loaded_count());
+        assert_eq!(&'#', world.get(Point::new(-1, -1)));
+        assert!(world.is_loaded(ChunkCoord::new(-1, -1)));
+
+        let mut marked: Vec<Point> = world
+            .iter()
+            .filter(|(_, &tile)| tile != '.')
+            .map(|(point, _)| point)
+            .collect();
+        marked.sort_by_key(|p| (p.y, p.x));
+        assert_eq!(marked, [(-1, -1), (3, 3)]);
+
+        let chunk = world.unload(ChunkCoord::new(0, 0)).unwrap();
+        assert_eq!(&'.', world.get(Point::new(3, 3)));
+        assert!(world.insert_chunk(ChunkCoord::new(0, 0), chunk));
+        assert_eq!(&'@', world.get(Point::new(3, 3)));
+    }
+}
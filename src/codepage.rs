@@ -0,0 +1,382 @@
+//! Single-byte code pages for console fonts. All of them share the CP437 glyphs
+//! for bytes 0x00–0x7F and differ in the upper half.
+
+use super::cp437::{self, DecodeMode, UnmappableChar};
+
+/// Conversion between Unicode characters and bytes of a code page
+pub trait CodePage {
+    /// Converts a unicode character to a byte of the code page, returning 0 if it didn't have a match
+    fn to_byte(&self, c: char) -> u8;
+
+    /// Converts a byte of the code page to its Unicode character equivalent
+    fn to_char(&self, c: u8) -> char;
+
+    /// Converts a unicode character to a byte of the code page
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the character has no equivalent in the code page
+    fn try_to_byte(&self, c: char) -> Result<u8, UnmappableChar> {
+        match self.to_byte(c) {
+            0 if c != '\0' => Err(UnmappableChar {
+                position: 0,
+                char: c,
+            }),
+            byte => Ok(byte),
+        }
+    }
+
+    /// Converts a string into bytes of the code page, unknown characters become 0
+    fn string_to_bytes<S: AsRef<str>>(&self, input: S) -> Vec<u8>
+    where
+        Self: Sized,
+    {
+        input.as_ref().chars().map(|c| self.to_byte(c)).collect()
+    }
+
+    /// Converts a string into bytes of the code page
+    ///
+    /// # Errors
+    ///
+    /// Returns every character that has no equivalent in the code page
+    fn try_string_to_bytes<S: AsRef<str>>(&self, input: S) -> Result<Vec<u8>, Vec<UnmappableChar>>
+    where
+        Self: Sized,
+    {
+        let (bytes, unmappable) = encode_with(input.as_ref(), 0, |c| self.try_to_byte(c).ok());
+        if unmappable.is_empty() {
+            Ok(bytes)
+        } else {
+            Err(unmappable)
+        }
+    }
+
+    /// Converts bytes of the code page into a string
+    fn bytes_to_string(&self, bytes: &[u8]) -> String {
+        bytes.iter().map(|&c| self.to_char(c)).collect()
+    }
+
+    /// Converts a byte of the code page to a Unicode character, treating control codes according to the mode
+    fn to_char_with(&self, c: u8, mode: DecodeMode) -> char {
+        match mode {
+            DecodeMode::ControlCodes if cp437::is_control(c) => char::from(c),
+            _ => self.to_char(c),
+        }
+    }
+
+    /// Converts a unicode character to a byte of the code page, returning 0 if it didn't have a match.
+    /// With [`DecodeMode::ControlCodes`] ASCII control characters are kept as they are.
+    fn to_byte_with(&self, c: char, mode: DecodeMode) -> u8 {
+        match (mode, u8::try_from(c)) {
+            (DecodeMode::ControlCodes, Ok(byte)) if cp437::is_control(byte) => byte,
+            _ => self.to_byte(c),
+        }
+    }
+
+    /// Converts bytes of the code page into a string, treating control codes according to the mode
+    fn bytes_to_string_with(&self, bytes: &[u8], mode: DecodeMode) -> String {
+        bytes.iter().map(|&c| self.to_char_with(c, mode)).collect()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Configurable conversion of text to a code page, CP437 unless set with [`Encoder::with_code_page`]
+pub struct Encoder<P = Cp437> {
+    pub code_page: P,
+    /// Byte used for characters without an equivalent in the code page
+    pub replacement: u8,
+    /// Use [`cp437::approximate`] before falling back to the replacement
+    pub lossy: bool,
+    /// With [`DecodeMode::ControlCodes`] ASCII control characters such as newlines are kept
+    pub mode: DecodeMode,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self {
+            code_page: Cp437,
+            replacement: b'?',
+            lossy: false,
+            mode: DecodeMode::Graphics,
+        }
+    }
+}
+
+impl Encoder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<P: CodePage> Encoder<P> {
+    #[must_use]
+    pub fn with_code_page<Q: CodePage>(self, code_page: Q) -> Encoder<Q> {
+        Encoder {
+            code_page,
+            replacement: self.replacement,
+            lossy: self.lossy,
+            mode: self.mode,
+        }
+    }
+
+    #[must_use]
+    pub fn with_replacement(mut self, replacement: u8) -> Self {
+        self.replacement = replacement;
+        self
+    }
+
+    #[must_use]
+    pub fn with_lossy(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+
+    #[must_use]
+    pub fn with_mode(mut self, mode: DecodeMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Converts a unicode character, `None` if it had to be replaced
+    fn convert(&self, c: char) -> Option<u8> {
+        if self.mode == DecodeMode::ControlCodes && c.is_ascii_control() {
+            return Some(self.code_page.to_byte_with(c, self.mode));
+        }
+        self.code_page.try_to_byte(c).ok().or_else(|| {
+            cp437::approximate(c)
+                .filter(|_| self.lossy)
+                .and_then(|similar| self.code_page.try_to_byte(similar).ok())
+        })
+    }
+
+    #[must_use]
+    /// Converts a unicode character, using the replacement byte if it has no equivalent
+    pub fn encode_char(&self, c: char) -> u8 {
+        self.convert(c).unwrap_or(self.replacement)
+    }
+
+    #[must_use]
+    /// Converts a string, returning the bytes and all characters that were replaced
+    pub fn encode<S: AsRef<str>>(&self, input: S) -> (Vec<u8>, Vec<UnmappableChar>) {
+        encode_with(input.as_ref(), self.replacement, |c| self.convert(c))
+    }
+}
+
+/// Converts every character, collecting the ones `convert` can't map with their positions
+fn encode_with<F>(input: &str, replacement: u8, mut convert: F) -> (Vec<u8>, Vec<UnmappableChar>)
+where
+    F: FnMut(char) -> Option<u8>,
+{
+    let mut unmappable = Vec::new();
+    let bytes = input
+        .chars()
+        .enumerate()
+        .map(|(position, c)| {
+            convert(c).unwrap_or_else(|| {
+                unmappable.push(UnmappableChar { position, char: c });
+                replacement
+            })
+        })
+        .collect();
+    (bytes, unmappable)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Original IBM PC code page, see the [`cp437`] module
+pub struct Cp437;
+
+impl CodePage for Cp437 {
+    fn to_byte(&self, c: char) -> u8 {
+        cp437::to_byte(c)
+    }
+
+    fn to_char(&self, c: u8) -> char {
+        cp437::to_char(c)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Western European code page, trades some box-drawing and Greek glyphs for accented letters
+pub struct Cp850;
+
+impl CodePage for Cp850 {
+    fn to_byte(&self, c: char) -> u8 {
+        upper_half_to_byte(c, &CP850_UPPER)
+    }
+
+    fn to_char(&self, c: u8) -> char {
+        upper_half_to_char(c, &CP850_UPPER)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Cyrillic (Russian) code page
+pub struct Cp866;
+
+impl CodePage for Cp866 {
+    fn to_byte(&self, c: char) -> u8 {
+        upper_half_to_byte(c, &CP866_UPPER)
+    }
+
+    fn to_char(&self, c: u8) -> char {
+        upper_half_to_char(c, &CP866_UPPER)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Code page chosen at runtime, e.g. from player settings
+pub enum AnyCodePage {
+    #[default]
+    Cp437,
+    Cp850,
+    Cp866,
+}
+
+impl CodePage for AnyCodePage {
+    fn to_byte(&self, c: char) -> u8 {
+        match self {
+            AnyCodePage::Cp437 => Cp437.to_byte(c),
+            AnyCodePage::Cp850 => Cp850.to_byte(c),
+            AnyCodePage::Cp866 => Cp866.to_byte(c),
+        }
+    }
+
+    fn to_char(&self, c: u8) -> char {
+        match self {
+            AnyCodePage::Cp437 => Cp437.to_char(c),
+            AnyCodePage::Cp850 => Cp850.to_char(c),
+            AnyCodePage::Cp866 => Cp866.to_char(c),
+        }
+    }
+}
+
+/// Converts a character using the table of bytes 0x80–0xFF or the shared lower half.
+/// Characters found in both, like '¶' in CP850, map to the upper half.
+fn upper_half_to_byte(c: char, upper: &[char; 128]) -> u8 {
+    if let Some(index) = upper.iter().position(|&known| known == c) {
+        return u8::try_from(index + 128).unwrap_or(0);
+    }
+    match cp437::to_byte(c) {
+        byte @ 0..=127 => byte,
+        _ => 0,
+    }
+}
+
+fn upper_half_to_char(c: u8, upper: &[char; 128]) -> char {
+    if c < 128 {
+        cp437::to_char(c)
+    } else {
+        upper[usize::from(c - 128)]
+    }
+}
+
+#[rustfmt::skip]
+const CP850_UPPER: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', 'ø', '£', 'Ø', '×', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '®', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', 'Á', 'Â', 'À', '©', '╣', '║', '╗', '╝', '¢', '¥', '┐',
+    '└', '┴', '┬', '├', '─', '┼', 'ã', 'Ã', '╚', '╔', '╩', '╦', '╠', '═', '╬', '¤',
+    'ð', 'Ð', 'Ê', 'Ë', 'È', 'ı', 'Í', 'Î', 'Ï', '┘', '┌', '█', '▄', '¦', 'Ì', '▀',
+    'Ó', 'ß', 'Ô', 'Ò', 'õ', 'Õ', 'µ', 'þ', 'Þ', 'Ú', 'Û', 'Ù', 'ý', 'Ý', '¯', '´',
+    '\u{ad}', '±', '‗', '¾', '¶', '§', '÷', '¸', '°', '¨', '·', '¹', '³', '²', '■', '\u{a0}',
+];
+
+#[rustfmt::skip]
+const CP866_UPPER: [char; 128] = [
+    'А', 'Б', 'В', 'Г', 'Д', 'Е', 'Ж', 'З', 'И', 'Й', 'К', 'Л', 'М', 'Н', 'О', 'П',
+    'Р', 'С', 'Т', 'У', 'Ф', 'Х', 'Ц', 'Ч', 'Ш', 'Щ', 'Ъ', 'Ы', 'Ь', 'Э', 'Ю', 'Я',
+    'а', 'б', 'в', 'г', 'д', 'е', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о', 'п',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я',
+    'Ё', 'ё', 'Є', 'є', 'Ї', 'ї', 'Ў', 'ў', '°', '∙', '·', '√', '№', '¤', '■', '\u{a0}',
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{AnyCodePage, CodePage, Cp437, Cp850, Cp866, Encoder};
+    use crate::cp437::{DecodeMode, FontAtlas, UnmappableChar};
+    use crate::{Point, Vec2};
+
+    /// Every byte survives conversion to a character and back, except for
+    /// lower half glyphs which the code page also has in the upper half
+    fn roundtrip_all(page: &dyn CodePage, duplicates: &[u8]) {
+        for b in 0u8..=255 {
+            let c = page.to_char(b);
+            if duplicates.contains(&b) {
+                assert_eq!(c, page.to_char(page.to_byte(c)));
+            } else {
+                assert_eq!(page.to_byte(c), b);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_cp437() {
+        roundtrip_all(&Cp437, &[]);
+        roundtrip_all(&AnyCodePage::default(), &[]);
+    }
+
+    #[test]
+    fn roundtrip_cp850() {
+        // Pilcrow and section sign
+        roundtrip_all(&Cp850, &[0x14, 0x15]);
+        assert_eq!(0xF4, Cp850.to_byte('¶'));
+        assert_eq!(vec![0x9D, 0xB7, 0xE3], Cp850.string_to_bytes("ØÀÒ"));
+        assert_eq!(
+            "Straße ±",
+            Cp850.bytes_to_string(&Cp850.string_to_bytes("Straße ±"))
+        );
+        assert!(Cp850.try_to_byte('╤').is_err());
+    }
+
+    #[test]
+    fn roundtrip_cp866() {
+        roundtrip_all(&Cp866, &[]);
+        roundtrip_all(&AnyCodePage::Cp866, &[]);
+        let bytes = Cp866.string_to_bytes("Привет, мир!");
+        assert_eq!([0x8F, 0xE0, 0xA8, 0xA2, 0xA5, 0xE2], bytes[..6]);
+        assert_eq!("Привет, мир!", Cp866.bytes_to_string(&bytes));
+        assert_eq!(Ok(0xFC), Cp866.try_to_byte('№'));
+        assert!(Cp866.try_to_byte('é').is_err());
+    }
+
+    #[test]
+    fn other_pages_everywhere() {
+        let encoder = Encoder::new()
+            .with_code_page(Cp866)
+            .with_lossy(true)
+            .with_mode(DecodeMode::ControlCodes);
+        let (bytes, unmappable) = encoder.encode("Щ\nē€");
+        assert_eq!(vec![0x99, b'\n', b'e', b'?'], bytes);
+        assert_eq!(
+            vec![UnmappableChar {
+                position: 3,
+                char: '€'
+            }],
+            unmappable
+        );
+        assert_eq!(Ok(vec![0x82, 0xA2]), Cp850.try_string_to_bytes("éó"));
+        assert!(Cp866.try_string_to_bytes(String::from("é")).is_err());
+
+        assert_eq!(
+            '\t',
+            AnyCodePage::Cp850.to_char_with(9, DecodeMode::ControlCodes)
+        );
+        assert_eq!(
+            "Я\r\n",
+            Cp866.bytes_to_string_with(&[0x9F, 13, 10], DecodeMode::ControlCodes)
+        );
+        assert_eq!(10, Cp850.to_byte_with('\n', DecodeMode::ControlCodes));
+
+        let atlas = FontAtlas::new(Vec2::new(8.0, 8.0)).with_code_page(Cp866);
+        assert_eq!(Some(Point::new(15, 9)), atlas.char_cell('Я'));
+    }
+}
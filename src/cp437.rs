@@ -1,3 +1,8 @@
+use super::codepage::{CodePage, Cp437};
+use super::{Direction, Point, Rect, Vec2, DIR4};
+
+pub use super::codepage::Encoder;
+
 #[must_use]
 #[allow(clippy::too_many_lines)]
 /// Converts a unicode character to a CP437 equivalent, returning 0 if it didn't have a match
@@ -566,9 +571,300 @@ pub fn string_to_bytes<S: AsRef<str>>(input: S) -> Vec<u8> {
     input.as_ref().chars().map(to_byte).collect()
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Character without a CP437 equivalent
+pub struct UnmappableChar {
+    /// Index of the character (not the byte) in the input, 0 for a single character
+    pub position: usize,
+    pub char: char,
+}
+
+/// Converts a unicode character to a CP437 equivalent
+///
+/// # Errors
+///
+/// Returns an error if the character has no CP437 equivalent
+pub fn try_to_byte(c: char) -> Result<u8, UnmappableChar> {
+    match to_byte(c) {
+        0 if c != '\0' => Err(UnmappableChar {
+            position: 0,
+            char: c,
+        }),
+        byte => Ok(byte),
+    }
+}
+
+/// Converts a string into CP437 bytes
+///
+/// # Errors
+///
+/// Returns every character that has no CP437 equivalent
+pub fn try_string_to_bytes<S: AsRef<str>>(input: S) -> Result<Vec<u8>, Vec<UnmappableChar>> {
+    Cp437.try_string_to_bytes(input)
+}
+
+#[must_use]
+/// Converts CP437 bytes into a string
+pub fn bytes_to_string(bytes: &[u8]) -> String {
+    bytes.iter().copied().map(to_char).collect()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Meaning of bytes 0x00–0x1F and 0x7F, which are both ASCII control codes and CP437 glyphs
+pub enum DecodeMode {
+    /// Every byte is a glyph, e.g. for screen dumps: 0x0A is '◙'
+    #[default]
+    Graphics,
+    /// Bytes 0x00–0x1F and 0x7F are control codes, e.g. for text files: 0x0A is a newline
+    ControlCodes,
+}
+
+#[inline]
+#[must_use]
+/// Check if the byte is an ASCII control code, 0x00–0x1F or 0x7F
+pub const fn is_control(c: u8) -> bool {
+    c.is_ascii_control()
+}
+
+#[must_use]
+/// Converts a CP437 byte to a Unicode character, treating control codes according to the mode
+pub fn to_char_with(c: u8, mode: DecodeMode) -> char {
+    Cp437.to_char_with(c, mode)
+}
+
+#[must_use]
+/// Converts a unicode character to a CP437 equivalent, returning 0 if it didn't have a match.
+/// With [`DecodeMode::ControlCodes`] ASCII control characters are kept as they are.
+pub fn to_byte_with(c: char, mode: DecodeMode) -> u8 {
+    Cp437.to_byte_with(c, mode)
+}
+
+#[must_use]
+/// Converts CP437 bytes into a string, treating control codes according to the mode
+pub fn bytes_to_string_with(bytes: &[u8], mode: DecodeMode) -> String {
+    Cp437.bytes_to_string_with(bytes, mode)
+}
+
+#[must_use]
+/// Similar looking character for letters with diacritics and typographic punctuation missing in CP437,
+/// e.g. 'ā' becomes 'a' and '“' becomes '"'
+pub fn approximate(c: char) -> Option<char> {
+    Some(match c {
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ā' | 'Ă' | 'Ą' => 'A',
+        'ã' | 'ā' | 'ă' | 'ą' => 'a',
+        'Ć' | 'Ĉ' | 'Ċ' | 'Č' => 'C',
+        'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'Ď' | 'Đ' => 'D',
+        'ď' | 'đ' => 'd',
+        'È' | 'Ê' | 'Ë' | 'Ē' | 'Ė' | 'Ę' | 'Ě' => 'E',
+        'ē' | 'ė' | 'ę' | 'ě' => 'e',
+        'Ğ' | 'Ģ' => 'G',
+        'ğ' | 'ģ' => 'g',
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'Į' | 'İ' => 'I',
+        'ī' | 'į' | 'ı' => 'i',
+        'Ĺ' | 'Ľ' | 'Ł' => 'L',
+        'ĺ' | 'ľ' | 'ł' => 'l',
+        'Ń' | 'Ň' => 'N',
+        'ń' | 'ň' => 'n',
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ø' | 'Ō' | 'Ő' => 'O',
+        'õ' | 'ø' | 'ō' | 'ő' => 'o',
+        'Ŕ' | 'Ř' => 'R',
+        'ŕ' | 'ř' => 'r',
+        'Ś' | 'Ş' | 'Š' => 'S',
+        'ś' | 'ş' | 'š' => 's',
+        'Ţ' | 'Ť' => 'T',
+        'ţ' | 'ť' => 't',
+        'Ù' | 'Ú' | 'Û' | 'Ū' | 'Ů' | 'Ű' | 'Ų' => 'U',
+        'ū' | 'ů' | 'ű' | 'ų' => 'u',
+        'Ý' | 'Ÿ' => 'Y',
+        'ý' => 'y',
+        'Ź' | 'Ż' | 'Ž' => 'Z',
+        'ź' | 'ż' | 'ž' => 'z',
+        '‘' | '’' | '‚' | '′' => '\'',
+        '“' | '”' | '„' | '″' => '"',
+        '‐' | '–' | '—' | '−' => '-',
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Style of a box-drawing line
+pub enum Line {
+    Single,
+    Double,
+}
+
+#[must_use]
+/// Chooses the box-drawing byte connecting to cardinal neighbours for which `line` returns a style,
+/// e.g. to draw walls. Diagonal directions are never asked.
+/// CP437 can't mix styles on one axis, so a double line on either side makes the whole axis double.
+/// A tile without neighbours is drawn as a pillar, `■`.
+pub fn box_drawing<F>(mut line: F) -> u8
+where
+    F: FnMut(Direction) -> Option<Line>,
+{
+    let [east, south, west, north] = DIR4.map(&mut line);
+    let horizontal = axis_line(east, west);
+    let vertical = axis_line(south, north);
+
+    // Variants ordered: both single, both double, double horizontal, double vertical
+    let variants = match (
+        east.is_some(),
+        south.is_some(),
+        west.is_some(),
+        north.is_some(),
+    ) {
+        (false, false, false, false) => return to_byte('■'),
+        (_, false, _, false) => ['─', '═', '═', '─'],
+        (false, _, false, _) => ['│', '║', '│', '║'],
+        (true, true, false, false) => ['┌', '╔', '╒', '╓'],
+        (false, true, true, false) => ['┐', '╗', '╕', '╖'],
+        (true, false, false, true) => ['└', '╚', '╘', '╙'],
+        (false, false, true, true) => ['┘', '╝', '╛', '╜'],
+        (true, true, true, false) => ['┬', '╦', '╤', '╥'],
+        (true, true, false, true) => ['├', '╠', '╞', '╟'],
+        (false, true, true, true) => ['┤', '╣', '╡', '╢'],
+        (true, false, true, true) => ['┴', '╩', '╧', '╨'],
+        (true, true, true, true) => ['┼', '╬', '╪', '╫'],
+    };
+    let c = match (horizontal, vertical) {
+        (Some(Line::Double), Some(Line::Double)) => variants[1],
+        (Some(Line::Double), _) => variants[2],
+        (_, Some(Line::Double)) => variants[3],
+        _ => variants[0],
+    };
+    to_byte(c)
+}
+
+/// Style of an axis with lines on its two sides
+fn axis_line(a: Option<Line>, b: Option<Line>) -> Option<Line> {
+    match (a, b) {
+        (Some(Line::Double), _) | (_, Some(Line::Double)) => Some(Line::Double),
+        (None, None) => None,
+        _ => Some(Line::Single),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Order of glyphs in a font sprite sheet
+pub enum AtlasLayout {
+    /// Bytes go left to right, then top to bottom
+    #[default]
+    RowMajor,
+    /// Bytes go top to bottom, then left to right
+    ColumnMajor,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// Sprite sheet with glyphs of a code page, CP437 unless set with [`FontAtlas::with_code_page`],
+/// in a grid of `columns` x `rows` cells of `glyph_size` pixels.
+/// Sheets with less than 256 cells only contain the first bytes.
+pub struct FontAtlas<P = Cp437> {
+    pub columns: i32,
+    pub rows: i32,
+    pub layout: AtlasLayout,
+    pub glyph_size: Vec2,
+    /// Code page used to look up characters
+    pub code_page: P,
+}
+
+impl FontAtlas {
+    #[must_use]
+    /// Standard 16x16 row-major sheet
+    pub fn new(glyph_size: Vec2) -> Self {
+        Self {
+            columns: 16,
+            rows: 16,
+            layout: AtlasLayout::RowMajor,
+            glyph_size,
+            code_page: Cp437,
+        }
+    }
+}
+
+impl<P: CodePage> FontAtlas<P> {
+    #[must_use]
+    pub fn with_dimensions(mut self, columns: i32, rows: i32) -> Self {
+        self.columns = columns;
+        self.rows = rows;
+        self
+    }
+
+    #[must_use]
+    pub fn with_layout(mut self, layout: AtlasLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    #[must_use]
+    pub fn with_code_page<Q: CodePage>(self, code_page: Q) -> FontAtlas<Q> {
+        FontAtlas {
+            columns: self.columns,
+            rows: self.rows,
+            layout: self.layout,
+            glyph_size: self.glyph_size,
+            code_page,
+        }
+    }
+
+    #[must_use]
+    /// Cell of the sheet holding the byte, `None` if the sheet is too small
+    pub fn cell(&self, byte: u8) -> Option<Point> {
+        let index = i32::from(byte);
+        if self.columns <= 0 || self.rows <= 0 || index >= self.columns * self.rows {
+            return None;
+        }
+        Some(match self.layout {
+            AtlasLayout::RowMajor => Point::new(index % self.columns, index / self.columns),
+            AtlasLayout::ColumnMajor => Point::new(index / self.rows, index % self.rows),
+        })
+    }
+
+    #[must_use]
+    /// Cell of the sheet holding the character's byte in the code page
+    pub fn char_cell(&self, c: char) -> Option<Point> {
+        self.cell(self.code_page.to_byte(c))
+    }
+
+    #[must_use]
+    /// Byte drawn in the cell of the sheet
+    pub fn byte_at(&self, cell: Point) -> Option<u8> {
+        if cell.x < 0 || cell.y < 0 || cell.x >= self.columns || cell.y >= self.rows {
+            return None;
+        }
+        let index = match self.layout {
+            AtlasLayout::RowMajor => cell.y * self.columns + cell.x,
+            AtlasLayout::ColumnMajor => cell.x * self.rows + cell.y,
+        };
+        u8::try_from(index).ok()
+    }
+
+    #[must_use]
+    /// Source rectangle of the byte in pixels
+    pub fn pixel_rect(&self, byte: u8) -> Option<Rect> {
+        self.cell(byte).map(|cell| {
+            let position = Vec2::from(cell) * self.glyph_size;
+            Rect::new(position.x, position.y, self.glyph_size.x, self.glyph_size.y)
+        })
+    }
+
+    #[must_use]
+    /// Source rectangle of the character's byte in the code page in pixels
+    pub fn char_pixel_rect(&self, c: char) -> Option<Rect> {
+        self.pixel_rect(self.code_page.to_byte(c))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{string_to_bytes, to_char};
+    use super::{
+        box_drawing, bytes_to_string, bytes_to_string_with, string_to_bytes, to_byte_with, to_char,
+        to_char_with, try_string_to_bytes, try_to_byte, AtlasLayout, DecodeMode, Direction,
+        Encoder, FontAtlas, Line, Point, Rect, UnmappableChar, Vec2,
+    };
 
     #[test]
     fn test_hello() {
@@ -597,7 +893,7 @@ mod tests {
         let test2: Vec<char> = vec![
             '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼',
         ];
-        for (ch, i) in test.into_iter().zip(test2.into_iter()) {
+        for (ch, i) in test.into_iter().zip(test2) {
             assert_eq!(to_char(ch), i);
         }
     }
@@ -608,4 +904,104 @@ mod tests {
             assert_eq!(super::to_byte(super::to_char(b)), b);
         }
     }
+
+    #[test]
+    fn test_box_drawing() {
+        let walls = |east, south, west, north| {
+            box_drawing(|dir| match dir {
+                Direction::East => east,
+                Direction::South => south,
+                Direction::West => west,
+                Direction::North => north,
+                _ => unreachable!(),
+            })
+        };
+        let (s, d) = (Some(Line::Single), Some(Line::Double));
+        assert_eq!('╔', to_char(walls(d, d, None, None)));
+        assert_eq!('║', to_char(walls(None, d, None, d)));
+        assert_eq!('┼', to_char(walls(s, s, s, s)));
+        assert_eq!('╤', to_char(walls(d, s, d, None)));
+        assert_eq!('╟', to_char(walls(s, d, None, d)));
+        assert_eq!('═', to_char(walls(s, None, d, None)));
+        assert_eq!('■', to_char(walls(None, None, None, None)));
+    }
+
+    #[test]
+    fn test_atlas() {
+        let atlas = FontAtlas::new(Vec2::new(8.0, 12.0));
+        assert_eq!(Some(Point::new(1, 4)), atlas.char_cell('A'));
+        assert_eq!(
+            Some(Rect::new(8.0, 48.0, 8.0, 12.0)),
+            atlas.char_pixel_rect('A')
+        );
+        assert_eq!(Some(Point::new(15, 15)), atlas.cell(255));
+
+        let columns = atlas.with_layout(AtlasLayout::ColumnMajor);
+        assert_eq!(Some(Point::new(4, 1)), columns.char_cell('A'));
+        assert_eq!(Some(65), columns.byte_at(Point::new(4, 1)));
+
+        let small = atlas.with_dimensions(32, 4);
+        assert_eq!(Some(Point::new(1, 2)), small.char_cell('A'));
+        assert_eq!(None, small.cell(128));
+        assert_eq!(None, small.byte_at(Point::new(32, 0)));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Ok(0), try_to_byte('\0'));
+        assert_eq!(Ok(130), try_to_byte('é'));
+        assert!(try_to_byte('ā').is_err());
+        assert_eq!(
+            Err(vec![
+                UnmappableChar {
+                    position: 1,
+                    char: 'ā'
+                },
+                UnmappableChar {
+                    position: 3,
+                    char: '€'
+                }
+            ]),
+            try_string_to_bytes("bāt€")
+        );
+        assert_eq!(Ok(vec![72, 105]), try_string_to_bytes("Hi"));
+    }
+
+    #[test]
+    fn test_encoder() {
+        let (bytes, unmappable) = Encoder::new().encode("Łódź €5");
+        assert_eq!(b"?\xA2d? ?5".to_vec(), bytes);
+        assert_eq!(3, unmappable.len());
+
+        let lossy = Encoder::new().with_lossy(true).with_replacement(b'_');
+        let (bytes, unmappable) = lossy.encode("Łódź €5");
+        assert_eq!("Lódz _5", bytes_to_string(&bytes));
+        assert_eq!(
+            vec![UnmappableChar {
+                position: 5,
+                char: '€'
+            }],
+            unmappable
+        );
+        assert_eq!(b'"', lossy.encode_char('“'));
+    }
+
+    #[test]
+    fn test_decode_modes() {
+        let dump = [b'A', 0x0A, 0x7F, 0x01];
+        assert_eq!("A◙⌂☺", bytes_to_string_with(&dump, DecodeMode::Graphics));
+        assert_eq!(
+            "A\n\u{7f}\u{1}",
+            bytes_to_string_with(&dump, DecodeMode::ControlCodes)
+        );
+        assert_eq!('\t', to_char_with(9, DecodeMode::ControlCodes));
+        assert_eq!('○', to_char_with(9, DecodeMode::Graphics));
+        assert_eq!(10, to_byte_with('\n', DecodeMode::ControlCodes));
+        assert_eq!(0, to_byte_with('\n', DecodeMode::Graphics));
+        assert_eq!(10, to_byte_with('◙', DecodeMode::ControlCodes));
+
+        let text = Encoder::new().with_mode(DecodeMode::ControlCodes);
+        assert_eq!((b"a\r\nb".to_vec(), vec![]), text.encode("a\r\nb"));
+        assert_eq!(1, Encoder::new().encode("a\nb").1.len());
+    }
 }
@@ -2,6 +2,13 @@ use std::cmp::Ordering::{Equal, Greater, Less};
 
 use super::{Point, Vec2};
 
+pub const DIR4: [Direction; 4] = [
+    Direction::East,
+    Direction::South,
+    Direction::West,
+    Direction::North,
+];
+
 pub const DIR8: [Direction; 8] = [
     Direction::East,
     Direction::SouthEast,
@@ -25,12 +32,13 @@ pub const DIR9: [Direction; 9] = [
     Direction::NorthEast,
 ];
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Here,
     North,
     NorthEast,
+    #[default]
     East,
     SouthEast,
     South,
@@ -56,12 +64,6 @@ impl Direction {
     }
 }
 
-impl Default for Direction {
-    fn default() -> Self {
-        Self::East
-    }
-}
-
 impl Direction {
     #[cfg(feature = "rand")]
     pub fn random<R: rand::Rng + ?Sized>(rng: &mut R, include_here: bool) -> Self {
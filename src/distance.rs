@@ -0,0 +1,53 @@
+use super::Point;
+
+const DIAGONAL_COST: f32 = std::f32::consts::SQRT_2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Distance metric used to measure how far two points are from each other.
+pub enum Distance {
+    /// Straight-line (pythagorean) distance
+    #[default]
+    Euclidean,
+    /// Sum of horizontal and vertical steps, movement without diagonals
+    Manhattan,
+    /// Largest of horizontal and vertical steps, diagonal moves cost the same as straight ones
+    Chebyshev,
+    /// Diagonal moves cost `sqrt(2)`, straight moves cost 1
+    Octile,
+}
+
+impl Distance {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    /// Distance between two points under this metric
+    pub fn between(self, a: Point, b: Point) -> f32 {
+        match self {
+            Distance::Euclidean => a.distance_to(b),
+            Distance::Manhattan => a.manhattan_distance_to(b) as f32,
+            Distance::Chebyshev => a.chebyshev_distance_to(b) as f32,
+            Distance::Octile => {
+                let dx = a.x.abs_diff(b.x);
+                let dy = a.y.abs_diff(b.y);
+                let (long, short) = if dx > dy { (dx, dy) } else { (dy, dx) };
+                (long - short) as f32 + short as f32 * DIAGONAL_COST
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Distance, Point};
+
+    #[test]
+    fn metrics() {
+        let a = Point::new(1, 2);
+        let b = Point::new(4, 6);
+        assert!((Distance::Euclidean.between(a, b) - 5.0).abs() < f32::EPSILON);
+        assert!((Distance::Manhattan.between(a, b) - 7.0).abs() < f32::EPSILON);
+        assert!((Distance::Chebyshev.between(a, b) - 4.0).abs() < f32::EPSILON);
+        let octile = 1.0 + 3.0 * std::f32::consts::SQRT_2;
+        assert!((Distance::Octile.between(a, b) - octile).abs() < f32::EPSILON);
+    }
+}
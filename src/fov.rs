@@ -0,0 +1,126 @@
+//! Field of view by recursive shadowcasting.
+
+use std::collections::HashSet;
+
+use super::Point;
+
+/// Multipliers turning octant-local `(dx, dy)` into world offsets: `xx, xy, yx, yy` per octant
+const OCTANTS: [[i32; 4]; 8] = [
+    [1, 0, 0, -1],
+    [0, 1, -1, 0],
+    [0, -1, -1, 0],
+    [-1, 0, 0, -1],
+    [-1, 0, 0, 1],
+    [0, -1, 1, 0],
+    [0, 1, 1, 0],
+    [1, 0, 0, 1],
+];
+
+#[must_use]
+/// Tiles visible from the origin within the Euclidean radius, the origin included.
+/// Opaque tiles are visible themselves but hide what's behind them.
+/// Points are sorted row by row.
+pub fn field_of_view<F>(origin: Point, radius: u32, mut is_transparent: F) -> Vec<Point>
+where
+    F: FnMut(Point) -> bool,
+{
+    let radius = i32::try_from(radius).unwrap_or(i32::MAX);
+    let mut visible = HashSet::new();
+    visible.insert(origin);
+    for octant in OCTANTS {
+        let mut caster = Caster {
+            origin,
+            radius,
+            octant,
+            is_transparent: &mut is_transparent,
+            visible: &mut visible,
+        };
+        caster.cast(1, 1.0, 0.0);
+    }
+    let mut visible: Vec<Point> = visible.into_iter().collect();
+    visible.sort_unstable_by_key(|point| (point.y, point.x));
+    visible
+}
+
+struct Caster<'a, F> {
+    origin: Point,
+    radius: i32,
+    octant: [i32; 4],
+    is_transparent: &'a mut F,
+    visible: &'a mut HashSet<Point>,
+}
+
+impl<F: FnMut(Point) -> bool> Caster<'_, F> {
+    /// Scan rows of the octant starting at `row`, between two slopes
+    #[allow(clippy::cast_precision_loss)]
+    fn cast(&mut self, row: i32, mut start: f32, end: f32) {
+        if start < end {
+            return;
+        }
+        let [xx, xy, yx, yy] = self.octant;
+        let radius_squared = i64::from(self.radius) * i64::from(self.radius);
+        let mut next_start = start;
+        for distance in row..=self.radius {
+            let dy = -distance;
+            let mut blocked = false;
+            for dx in -distance..=0 {
+                let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+                let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+                if start < right_slope {
+                    continue;
+                }
+                if end > left_slope {
+                    break;
+                }
+                let point = self.origin + Point::new(dx * xx + dy * xy, dx * yx + dy * yy);
+                if i64::from(dx).pow(2) + i64::from(dy).pow(2) <= radius_squared {
+                    self.visible.insert(point);
+                }
+                let transparent = (self.is_transparent)(point);
+                if blocked {
+                    if transparent {
+                        blocked = false;
+                        start = next_start;
+                    } else {
+                        next_start = right_slope;
+                    }
+                } else if !transparent && distance < self.radius {
+                    blocked = true;
+                    self.cast(distance + 1, start, left_slope);
+                    next_start = right_slope;
+                }
+            }
+            if blocked {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{field_of_view, Point};
+
+    #[test]
+    fn open_field() {
+        let visible = field_of_view(Point::new(0, 0), 2, |_| true);
+        // Radius 2 disc: 5 x 5 square without its 4 corners and 8 points next to them
+        assert_eq!(13, visible.len());
+        assert!(visible.contains(&Point::new(0, -2)));
+        assert!(!visible.contains(&Point::new(2, 1)));
+    }
+
+    #[test]
+    fn walls_cast_shadows() {
+        let wall = Point::new(2, 0);
+        let visible = field_of_view(Point::new(0, 0), 6, |p| p != wall);
+        assert!(visible.contains(&wall));
+        assert!(!visible.contains(&Point::new(4, 0)));
+        assert!(visible.contains(&Point::new(4, 2)));
+        assert!(visible.contains(&Point::new(-5, 0)));
+
+        // Closed room
+        let room = field_of_view(Point::new(0, 0), 10, |p| p.x.abs() < 2 && p.y.abs() < 2);
+        assert_eq!(25, room.len());
+    }
+}
@@ -0,0 +1,242 @@
+use std::ops::{Index, IndexMut};
+
+use super::{Point, TileRect, Transform};
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawGrid<T>"))]
+/// Rectangular buffer of tiles with `(0, 0)` at the top-left corner, indexed by `Point`.
+pub struct Grid<T> {
+    width: i32,
+    height: i32,
+    cells: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+/// Deserialized grid before its size is checked
+struct RawGrid<T> {
+    width: i32,
+    height: i32,
+    cells: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<RawGrid<T>> for Grid<T> {
+    type Error = &'static str;
+
+    fn try_from(raw: RawGrid<T>) -> Result<Self, Self::Error> {
+        let width = usize::try_from(raw.width).map_err(|_| "grid width is negative")?;
+        let height = usize::try_from(raw.height).map_err(|_| "grid height is negative")?;
+        if width.checked_mul(height) != Some(raw.cells.len()) {
+            return Err("grid cells don't match its size");
+        }
+        Ok(Grid {
+            width: raw.width,
+            height: raw.height,
+            cells: raw.cells,
+        })
+    }
+}
+
+impl<T> Grid<T> {
+    #[must_use]
+    /// Create a new grid filled with copies of `value`
+    pub fn new(width: i32, height: i32, value: T) -> Self
+    where
+        T: Clone,
+    {
+        let (width, height) = (width.max(0), height.max(0));
+        Grid {
+            width,
+            height,
+            cells: vec![value; TileRect::with_size(width, height).area()],
+        }
+    }
+
+    #[must_use]
+    /// Create a new grid calling `f` for every point, row by row
+    pub fn from_fn<F>(width: i32, height: i32, f: F) -> Self
+    where
+        F: FnMut(Point) -> T,
+    {
+        let (width, height) = (width.max(0), height.max(0));
+        Grid {
+            width,
+            height,
+            cells: TileRect::with_size(width, height).points().map(f).collect(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    #[inline]
+    #[must_use]
+    /// Rectangle covering all tiles of the grid
+    pub fn rect(&self) -> TileRect {
+        TileRect::with_size(self.width, self.height)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Check if the point is inside the grid
+    pub fn contains(&self, point: Point) -> bool {
+        self.rect().contains(point)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Index of the point in the underlying buffer, `None` if out of bounds
+    pub fn index_of(&self, point: Point) -> Option<usize> {
+        if self.contains(point) {
+            point.to_index(self.width)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.index_of(point).map(|i| &self.cells[i])
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.index_of(point).map(|i| &mut self.cells[i])
+    }
+
+    /// Replace the value at the point, returning the old one or `None` if out of bounds
+    pub fn set(&mut self, point: Point, value: T) -> Option<T> {
+        self.get_mut(point)
+            .map(|cell| std::mem::replace(cell, value))
+    }
+
+    /// Set every tile to a copy of `value`
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        self.cells.fill(value);
+    }
+
+    #[inline]
+    #[must_use]
+    /// Underlying buffer in row-major order
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    #[inline]
+    #[must_use]
+    /// Underlying buffer in row-major order
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    /// Iterate over all points of the grid, row by row
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        self.rect().points()
+    }
+
+    /// Iterate over all tiles with their points, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    /// Iterate mutably over all tiles with their points, row by row
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Point, &mut T)> {
+        self.rect().points().zip(self.cells.iter_mut())
+    }
+
+    #[must_use]
+    /// Create a new grid of the same size by converting every tile
+    pub fn map<U, F>(&self, mut f: F) -> Grid<U>
+    where
+        F: FnMut(Point, &T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.iter().map(|(point, value)| f(point, value)).collect(),
+        }
+    }
+
+    #[must_use]
+    /// Create a rotated or mirrored copy, width and height are swapped if needed
+    pub fn transformed(&self, transform: Transform) -> Self
+    where
+        T: Clone,
+    {
+        let (width, height) = transform.size(self.width, self.height);
+        let inverse = transform.inverse();
+        Grid::from_fn(width, height, |point| {
+            self[inverse.apply_in(point, width, height)].clone()
+        })
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &Self::Output {
+        self.get(point).expect("point is out of grid bounds")
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut Self::Output {
+        self.get_mut(point).expect("point is out of grid bounds")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, Point};
+
+    #[test]
+    fn get_and_set() {
+        let mut grid = Grid::new(3, 2, 0);
+        assert_eq!(Some(0), grid.set(Point::new(2, 1), 5));
+        assert_eq!(Some(&5), grid.get(Point::new(2, 1)));
+        assert_eq!(5, grid.cells()[5]);
+        assert_eq!(None, grid.set(Point::new(3, 0), 1));
+        assert_eq!(None, grid.get(Point::new(0, 2)));
+        assert_eq!(None, grid.get(Point::new(-1, 0)));
+    }
+
+    #[test]
+    fn from_fn() {
+        let grid = Grid::from_fn(2, 2, |p| p.x + p.y * 10);
+        assert_eq!(grid.cells(), [0, 1, 10, 11]);
+        assert_eq!(11, grid[Point::new(1, 1)]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn checked_deserialization() {
+        use super::RawGrid;
+
+        let raw = |width, height, cells| RawGrid {
+            width,
+            height,
+            cells,
+        };
+        let grid = Grid::try_from(raw(2, 1, vec![1, 2])).unwrap();
+        assert_eq!(Some(&2), grid.get(Point::new(1, 0)));
+        assert!(Grid::try_from(raw(2, 2, vec![1, 2])).is_err());
+        assert!(Grid::try_from(raw(-2, -1, vec![1, 2])).is_err());
+        assert!(Grid::<i32>::try_from(raw(0, 5, Vec::new())).is_ok());
+    }
+}
@@ -0,0 +1,395 @@
+//! Influence maps for strategy AI: sources of positive or negative influence spread over
+//! open tiles and fade with distance, and layers like threat and opportunity are combined
+//! into a single map to look for the best or worst spot in a region.
+
+use super::{Grid, Point, TileRect, DIR8};
+
+/// Momentum is kept below this, so the map still reacts to its sources
+const MAX_MOMENTUM: f32 = 0.99;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How influence fades with distance, diagonal steps count as `√2`
+pub enum Decay {
+    /// Influence multiplied by the factor per step
+    Exponential(f32),
+    /// Influence loses the amount per step until it reaches zero
+    Linear(f32),
+}
+
+impl Default for Decay {
+    fn default() -> Self {
+        Decay::Exponential(0.8)
+    }
+}
+
+impl Decay {
+    #[must_use]
+    /// Influence left of `value` after travelling `distance`, never changing its sign
+    pub fn apply(self, value: f32, distance: f32) -> f32 {
+        match self {
+            Decay::Exponential(factor) => value * factor.clamp(0.0, 1.0).powf(distance),
+            Decay::Linear(amount) => {
+                value.signum() * (value.abs() - amount.max(0.0) * distance).max(0.0)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawInfluenceMap"))]
+/// Influence of sources spreading through `DIR8` neighbours one step per [`update`](InfluenceMap::update).
+/// Positive and negative influence spread separately and add up on every tile.
+pub struct InfluenceMap {
+    positive: Grid<f32>,
+    negative: Grid<f32>,
+    /// Sum of both layers
+    influence: Grid<f32>,
+    sources: Vec<(Point, f32)>,
+    pub decay: Decay,
+    /// Share of the old value kept by every update, `0.0..1.0`
+    momentum: f32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+/// Deserialized map before its layers and momentum are checked
+struct RawInfluenceMap {
+    positive: Grid<f32>,
+    negative: Grid<f32>,
+    influence: Grid<f32>,
+    sources: Vec<(Point, f32)>,
+    decay: Decay,
+    momentum: f32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawInfluenceMap> for InfluenceMap {
+    type Error = &'static str;
+
+    fn try_from(raw: RawInfluenceMap) -> Result<Self, Self::Error> {
+        let size = raw.influence.rect();
+        if raw.positive.rect() != size || raw.negative.rect() != size {
+            return Err("influence layers differ in size");
+        }
+        if !(0.0..MAX_MOMENTUM).contains(&raw.momentum) {
+            return Err("momentum is out of range");
+        }
+        Ok(InfluenceMap {
+            positive: raw.positive,
+            negative: raw.negative,
+            influence: raw.influence,
+            sources: raw.sources,
+            decay: raw.decay,
+            momentum: raw.momentum,
+        })
+    }
+}
+
+impl InfluenceMap {
+    #[must_use]
+    pub fn new(width: i32, height: i32) -> Self {
+        InfluenceMap {
+            positive: Grid::new(width, height, 0.0),
+            negative: Grid::new(width, height, 0.0),
+            influence: Grid::new(width, height, 0.0),
+            sources: Vec::new(),
+            decay: Decay::default(),
+            momentum: 0.0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_decay(mut self, decay: Decay) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    #[must_use]
+    /// Share of the old value kept by every update, limited to `0.0..=0.99`.
+    /// Higher momentum makes the map react slowly to moving sources.
+    pub fn with_momentum(mut self, momentum: f32) -> Self {
+        self.momentum = momentum.clamp(0.0, MAX_MOMENTUM);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn momentum(&self) -> f32 {
+        self.momentum
+    }
+
+    /// Add a source, e.g. a unit's strength, negative for enemies.
+    /// Sources on the same tile add up.
+    pub fn add_source(&mut self, point: Point, value: f32) {
+        self.sources.push((point, value));
+    }
+
+    /// Remove all sources, e.g. before adding units at their new positions.
+    /// The influence itself stays and fades by momentum.
+    pub fn clear_sources(&mut self) {
+        self.sources.clear();
+    }
+
+    #[must_use]
+    pub fn sources(&self) -> &[(Point, f32)] {
+        &self.sources
+    }
+
+    #[must_use]
+    /// Influence at the point, `0.0` outside the map
+    pub fn get(&self, point: Point) -> f32 {
+        self.influence.get(point).copied().unwrap_or(0.0)
+    }
+
+    #[must_use]
+    /// Net influence, positive plus negative
+    pub fn grid(&self) -> &Grid<f32> {
+        &self.influence
+    }
+
+    #[must_use]
+    /// Influence of positive sources only
+    pub fn positive(&self) -> &Grid<f32> {
+        &self.positive
+    }
+
+    #[must_use]
+    /// Influence of negative sources only
+    pub fn negative(&self) -> &Grid<f32> {
+        &self.negative
+    }
+
+    /// Spread influence one step between open tiles, closed tiles have none
+    pub fn update<F>(&mut self, mut is_open: F)
+    where
+        F: FnMut(Point) -> bool,
+    {
+        let (width, height) = (self.influence.width(), self.influence.height());
+        let open = Grid::from_fn(width, height, &mut is_open);
+        let mut own = Grid::new(width, height, 0.0_f32);
+        for &(point, value) in &self.sources {
+            if let Some(cell) = own.get_mut(point) {
+                *cell += value;
+            }
+        }
+        self.positive = self.spread(&self.positive, &open, |point| own[point].max(0.0), f32::max);
+        self.negative = self.spread(&self.negative, &open, |point| own[point].min(0.0), f32::min);
+        self.influence = Grid::from_fn(width, height, |point| {
+            self.positive[point] + self.negative[point]
+        });
+    }
+
+    /// Next state of one layer, `stronger` picks the stronger of two values of its sign
+    fn spread<S, P>(&self, layer: &Grid<f32>, open: &Grid<bool>, own: S, stronger: P) -> Grid<f32>
+    where
+        S: Fn(Point) -> f32,
+        P: Fn(f32, f32) -> f32,
+    {
+        Grid::from_fn(layer.width(), layer.height(), |point| {
+            if !open[point] {
+                return 0.0;
+            }
+            let mut target = own(point);
+            for dir in DIR8 {
+                let next = point + dir;
+                if !open.get(next).copied().unwrap_or(false) {
+                    continue;
+                }
+                let step = if dir.is_diagonal() {
+                    std::f32::consts::SQRT_2
+                } else {
+                    1.0
+                };
+                target = stronger(target, self.decay.apply(layer[next], step));
+            }
+            layer[point] * self.momentum + target * (1.0 - self.momentum)
+        })
+    }
+
+    /// Update until the influence stops changing, at most once per tile of the map.
+    /// With momentum the influence only approaches its final values.
+    pub fn settle<F>(&mut self, mut is_open: F)
+    where
+        F: FnMut(Point) -> bool,
+    {
+        for _ in 0..self.influence.rect().area().max(1) {
+            let previous = self.influence.clone();
+            self.update(&mut is_open);
+            if self.influence == previous {
+                break;
+            }
+        }
+    }
+
+    #[must_use]
+    /// Point with the highest influence in the region, see [`highest`]
+    pub fn highest(&self, region: TileRect) -> Option<(Point, f32)> {
+        highest(&self.influence, region)
+    }
+
+    #[must_use]
+    /// Point with the lowest influence in the region, see [`lowest`]
+    pub fn lowest(&self, region: TileRect) -> Option<(Point, f32)> {
+        lowest(&self.influence, region)
+    }
+}
+
+#[must_use]
+/// Weighted sum of layers, as large as the smallest one.
+/// E.g. `combine(&[(opportunity, 1.0), (threat, -1.0)])`.
+pub fn combine(layers: &[(&Grid<f32>, f32)]) -> Grid<f32> {
+    let width = layers
+        .iter()
+        .map(|(grid, _)| grid.width())
+        .min()
+        .unwrap_or(0);
+    let height = layers
+        .iter()
+        .map(|(grid, _)| grid.height())
+        .min()
+        .unwrap_or(0);
+    Grid::from_fn(width, height, |point| {
+        layers
+            .iter()
+            .map(|&(grid, weight)| grid[point] * weight)
+            .sum()
+    })
+}
+
+#[must_use]
+/// Point with the highest value in the part of the region inside the grid,
+/// the first one row by row on ties
+pub fn highest(grid: &Grid<f32>, region: TileRect) -> Option<(Point, f32)> {
+    extreme(grid, region, |value, best| value > best)
+}
+
+#[must_use]
+/// Point with the lowest value in the part of the region inside the grid,
+/// the first one row by row on ties
+pub fn lowest(grid: &Grid<f32>, region: TileRect) -> Option<(Point, f32)> {
+    extreme(grid, region, |value, best| value < best)
+}
+
+fn extreme<F>(grid: &Grid<f32>, region: TileRect, better: F) -> Option<(Point, f32)>
+where
+    F: Fn(f32, f32) -> bool,
+{
+    let region = region.intersection(grid.rect())?;
+    region.points().map(|point| (point, grid[point])).fold(
+        None,
+        |best, (point, value)| match best {
+            Some((_, best_value)) if !better(value, best_value) => best,
+            _ => Some((point, value)),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combine, highest, Decay, InfluenceMap, Point, TileRect};
+
+    #[test]
+    fn decay_curves() {
+        assert!((Decay::Exponential(0.5).apply(8.0, 2.0) - 2.0).abs() < 1e-6);
+        assert!((Decay::Linear(3.0).apply(8.0, 2.0) - 2.0).abs() < 1e-6);
+        assert!((Decay::Linear(3.0).apply(-8.0, 2.0) + 2.0).abs() < 1e-6);
+        assert!(Decay::Linear(3.0).apply(-8.0, 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn spreading_around_walls() {
+        // Wall column at x = 4 with a gap at the top
+        let open = |p: Point| p.x != 4 || p.y == 0;
+        let mut map = InfluenceMap::new(9, 5).with_decay(Decay::Linear(1.0));
+        map.add_source(Point::new(1, 2), 5.0);
+        map.add_source(Point::new(7, 2), -3.0);
+        map.settle(open);
+        assert!((map.get(Point::new(1, 2)) - 5.0).abs() < 1e-5);
+        assert!((map.get(Point::new(2, 2)) - 4.0).abs() < 1e-5);
+        assert!((map.get(Point::new(7, 2)) + 3.0).abs() < 1e-5);
+        assert!(map.get(Point::new(4, 2)).abs() < 1e-6);
+        // Both sides reach the gap
+        assert!(map.get(Point::new(4, 0)) > 0.0);
+        assert!((map.get(Point::new(6, 2)) + 2.0).abs() < 1e-5);
+
+        let all = TileRect::with_size(9, 5);
+        assert_eq!(Some((Point::new(1, 2), 5.0)), map.highest(all));
+        assert_eq!(Some(Point::new(7, 2)), map.lowest(all).map(|(p, _)| p));
+        let right = TileRect::new(5, 0, 10, 10);
+        assert_eq!(Some((Point::new(7, 2), -3.0)), map.lowest(right));
+        assert_eq!(None, map.highest(TileRect::new(20, 20, 2, 2)));
+    }
+
+    #[test]
+    fn momentum_and_layers() {
+        let mut slow = InfluenceMap::new(5, 5).with_momentum(0.5);
+        slow.add_source(Point::new(2, 2), 4.0);
+        slow.update(|_| true);
+        assert!((slow.get(Point::new(2, 2)) - 2.0).abs() < 1e-6);
+        slow.update(|_| true);
+        assert!((slow.get(Point::new(2, 2)) - 3.0).abs() < 1e-6);
+
+        let mut threat = InfluenceMap::new(5, 5);
+        threat.add_source(Point::new(0, 0), 10.0);
+        threat.settle(|_| true);
+        let mut opportunity = InfluenceMap::new(5, 5);
+        opportunity.add_source(Point::new(1, 1), 4.0);
+        opportunity.add_source(Point::new(4, 4), 4.0);
+        opportunity.settle(|_| true);
+
+        let combined = combine(&[(opportunity.grid(), 1.0), (threat.grid(), -0.5)]);
+        let best = highest(&combined, combined.rect()).map(|(p, _)| p);
+        assert_eq!(Some(Point::new(4, 4)), best);
+        assert!(combine(&[]).cells().is_empty());
+    }
+
+    #[test]
+    fn opposing_sources() {
+        let mut map = InfluenceMap::new(6, 1).with_decay(Decay::Linear(1.0));
+        map.add_source(Point::new(0, 0), 5.0);
+        map.add_source(Point::new(2, 0), -5.0);
+        map.settle(|_| true);
+        let row: Vec<f32> = map.grid().cells().to_vec();
+        assert_eq!(vec![2.0, 0.0, -2.0, -2.0, -2.0, -2.0], row);
+        assert_eq!(&[5.0, 4.0, 3.0, 2.0, 1.0, 0.0], map.positive().cells());
+        assert!(map.get(Point::new(1, 0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn settle_reaches_far_tiles() {
+        // Winding corridor much longer than width plus height
+        let open = |p: Point| match p.y {
+            0 | 2 | 4 => true,
+            1 => p.x == 9,
+            3 => p.x == 0,
+            _ => false,
+        };
+        let mut map = InfluenceMap::new(10, 5).with_decay(Decay::Linear(0.1));
+        map.add_source(Point::new(0, 0), 5.0);
+        map.settle(open);
+        // About 25 steps away, more than `width + height` updates would spread
+        assert!(map.get(Point::new(9, 4)) > 0.0);
+        assert!((map.with_momentum(2.0).momentum() - 0.99).abs() < 1e-6);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn checked_deserialization() {
+        use super::{Grid, RawInfluenceMap};
+
+        let raw = |width, momentum| RawInfluenceMap {
+            positive: Grid::new(width, 2, 0.0),
+            negative: Grid::new(2, 2, 0.0),
+            influence: Grid::new(2, 2, 0.0),
+            sources: Vec::new(),
+            decay: Decay::default(),
+            momentum,
+        };
+        assert!(InfluenceMap::try_from(raw(2, 0.5)).is_ok());
+        assert!(InfluenceMap::try_from(raw(3, 0.5)).is_err());
+        assert!(InfluenceMap::try_from(raw(2, 1.0)).is_err());
+        assert!(InfluenceMap::try_from(raw(2, f32::NAN)).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use super::{Point, Vec2};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Projection of the tile grid onto the screen as diamonds.
+/// World x axis goes to the bottom-right of the screen, world y axis to the bottom-left.
+pub struct Projection {
+    /// Width of a tile diamond in pixels
+    pub tile_width: f32,
+    /// Height of a tile diamond in pixels
+    pub tile_height: f32,
+    /// Screen position of the center of tile `(0, 0)`
+    pub origin: Vec2,
+}
+
+impl Projection {
+    #[must_use]
+    /// Classic 2:1 "isometric" projection used by pixel art games
+    pub fn isometric(tile_width: f32) -> Self {
+        Self::dimetric(tile_width, tile_width / 2.0)
+    }
+
+    #[must_use]
+    /// Projection with any ratio of diamond width to height
+    pub fn dimetric(tile_width: f32, tile_height: f32) -> Self {
+        Self {
+            tile_width,
+            tile_height,
+            origin: Vec2::zero(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_origin(mut self, origin: Vec2) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    #[must_use]
+    /// Screen position of the tile center
+    pub fn to_screen(self, tile: Point) -> Vec2 {
+        self.position_to_screen(Vec2::from(tile))
+    }
+
+    #[must_use]
+    /// Screen position of a point in tile space, `(0.0, 0.0)` is the center of tile `(0, 0)`
+    pub fn position_to_screen(self, position: Vec2) -> Vec2 {
+        Vec2::new(
+            (position.x - position.y) * self.tile_width / 2.0,
+            (position.x + position.y) * self.tile_height / 2.0,
+        ) + self.origin
+    }
+
+    #[must_use]
+    /// Point in tile space shown at the screen position
+    pub fn screen_to_position(self, screen: Vec2) -> Vec2 {
+        let screen = screen - self.origin;
+        let across = screen.x / (self.tile_width / 2.0);
+        let down = screen.y / (self.tile_height / 2.0);
+        Vec2::new((down + across) / 2.0, (down - across) / 2.0)
+    }
+
+    #[must_use]
+    /// Tile whose diamond contains the screen position, e.g. for mouse picking
+    pub fn pick(self, screen: Vec2) -> Point {
+        Point::from(self.screen_to_position(screen))
+    }
+
+    #[must_use]
+    /// Corners of the tile diamond on screen: top, right, bottom, left
+    pub fn diamond(self, tile: Point) -> [Vec2; 4] {
+        let center = self.to_screen(tile);
+        let (half_width, half_height) = (self.tile_width / 2.0, self.tile_height / 2.0);
+        [
+            center - Vec2::new(0.0, half_height),
+            center + Vec2::new(half_width, 0.0),
+            center + Vec2::new(0.0, half_height),
+            center - Vec2::new(half_width, 0.0),
+        ]
+    }
+}
+
+#[inline]
+#[must_use]
+/// Draw order of the tile: tiles with lower keys are further from the viewer
+/// and must be drawn first
+pub fn draw_order(tile: Point) -> (i32, i32) {
+    (tile.x + tile.y, tile.x)
+}
+
+/// Sort tiles for painter's algorithm, back to front
+pub fn sort_for_drawing(tiles: &mut [Point]) {
+    tiles.sort_by_key(|&tile| draw_order(tile));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sort_for_drawing, Point, Projection, Vec2};
+
+    #[test]
+    fn isometric_roundtrip() {
+        let projection = Projection::isometric(64.0).with_origin(Vec2::new(400.0, 100.0));
+        assert_eq!(
+            Vec2::new(400.0, 100.0),
+            projection.to_screen(Point::new(0, 0))
+        );
+        assert_eq!(
+            Vec2::new(432.0, 116.0),
+            projection.to_screen(Point::new(1, 0))
+        );
+        assert_eq!(
+            Vec2::new(368.0, 116.0),
+            projection.to_screen(Point::new(0, 1))
+        );
+        for tile in [Point::new(3, -2), Point::new(-5, 7), Point::new(10, 10)] {
+            assert_eq!(tile, projection.pick(projection.to_screen(tile)));
+        }
+    }
+
+    #[test]
+    fn picking_diamond_edges() {
+        let projection = Projection::dimetric(40.0, 30.0);
+        // Just inside the right corner of tile (0, 0)
+        assert_eq!(Point::new(0, 0), projection.pick(Vec2::new(19.0, 0.0)));
+        // Just past it
+        assert_eq!(Point::new(1, -1), projection.pick(Vec2::new(21.0, 0.0)));
+        // Below the bottom corner
+        assert_eq!(Point::new(1, 1), projection.pick(Vec2::new(0.0, 16.0)));
+        let [top, right, bottom, left] = projection.diamond(Point::new(0, 0));
+        assert_eq!(
+            (top.y, right.x, bottom.y, left.x),
+            (-15.0, 20.0, 15.0, -20.0)
+        );
+    }
+
+    #[test]
+    fn draw_order() {
+        let mut tiles = vec![
+            Point::new(1, 1),
+            Point::new(0, 0),
+            Point::new(2, 0),
+            Point::new(0, 1),
+        ];
+        sort_for_drawing(&mut tiles);
+        assert_eq!(tiles, [(0, 0), (0, 1), (1, 1), (2, 0)]);
+    }
+}
@@ -1,12 +1,50 @@
-pub use direction::{Direction, DIR8, DIR9};
+pub use chunks::{ChunkCoord, ChunkedGrid};
+pub use direction::{Direction, DIR4, DIR8, DIR9};
+pub use distance::Distance;
+pub use grid::Grid;
 pub use point::Point;
+pub use quadtree::Quadtree;
+pub use rect::TileRect;
+pub use sight::{LineAlgorithm, LineOfSight};
+pub use spatial_hash::SpatialHash;
+pub use torus::Torus;
+pub use transform::Transform;
 pub use two_dim_direction::{ConvertError, TwoDimDirection};
+pub use viewport::Viewport;
 
+pub mod ansi;
+pub mod autotile;
+mod chunks;
 pub mod circles;
+pub mod codepage;
 pub mod cp437;
 mod direction;
+mod distance;
+pub mod fov;
+mod grid;
+pub mod influence;
+pub mod isometric;
+pub mod light;
+pub mod noise;
+pub mod pathfinding;
 mod point;
+pub mod prefab;
+pub mod propagation;
+mod quadtree;
+mod rect;
+#[cfg(feature = "rexpaint")]
+pub mod rexpaint;
+pub mod rooms;
+mod sight;
+mod spatial_hash;
+pub mod text;
+mod torus;
+mod transform;
 mod two_dim_direction;
+mod viewport;
+pub mod voronoi;
+#[cfg(feature = "rand")]
+pub mod wfc;
 
 pub type Vec2 = vek::Vec2<f32>;
 pub type Rect = vek::Rect<f32, f32>;
@@ -0,0 +1,483 @@
+//! Coloured light sources with falloff, occluded by walls using [`field_of_view`].
+
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+
+use super::{fov::field_of_view, Grid, Point};
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Linear RGB light, `1.0` per channel is full brightness but sums of lights can go above it
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+
+    #[must_use]
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        Color { r, g, b }
+    }
+
+    #[must_use]
+    /// Channels limited to `0.0..=1.0`
+    pub fn clamped(self) -> Self {
+        Color::new(
+            self.r.clamp(0.0, 1.0),
+            self.g.clamp(0.0, 1.0),
+            self.b.clamp(0.0, 1.0),
+        )
+    }
+}
+
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, rhs: Color) -> Color {
+        Color::new(self.r + rhs.r, self.g + rhs.g, self.b + rhs.b)
+    }
+}
+
+impl AddAssign for Color {
+    fn add_assign(&mut self, rhs: Color) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Color {
+    type Output = Color;
+
+    fn sub(self, rhs: Color) -> Color {
+        Color::new(self.r - rhs.r, self.g - rhs.g, self.b - rhs.b)
+    }
+}
+
+impl SubAssign for Color {
+    fn sub_assign(&mut self, rhs: Color) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: f32) -> Color {
+        Color::new(self.r * rhs, self.g * rhs, self.b * rhs)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How light fades with distance. `t` is the distance divided by `radius + 1`,
+/// so the outermost lit ring still gets some light.
+pub enum Falloff {
+    /// Full intensity up to the radius
+    Constant,
+    /// `1 - t`
+    #[default]
+    Linear,
+    /// `(1 - t)²`, bright core fading quickly
+    Quadratic,
+    /// `1 - (3t² - 2t³)`, smooth at the center and at the edge
+    Smooth,
+    /// `1 / (1 + d²)` on the distance itself, cut off at the radius
+    InverseSquare,
+}
+
+impl Falloff {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    /// Brightness factor in `0.0..=1.0` at a distance from a light of the given radius
+    pub fn factor(self, distance: f32, radius: u32) -> f32 {
+        let t = (distance / (radius as f32 + 1.0)).clamp(0.0, 1.0);
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - t,
+            Falloff::Quadratic => (1.0 - t) * (1.0 - t),
+            Falloff::Smooth => 1.0 - t * t * (3.0 - 2.0 * t),
+            Falloff::InverseSquare => 1.0 / (1.0 + distance * distance),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Light {
+    pub position: Point,
+    /// Euclidean radius in tiles
+    pub radius: u32,
+    /// Multiplier of the colour at the light's own tile, negative for lights darkening others
+    pub intensity: f32,
+    pub color: Color,
+}
+
+impl Light {
+    #[must_use]
+    pub const fn new(position: Point, radius: u32, intensity: f32, color: Color) -> Self {
+        Light {
+            position,
+            radius,
+            intensity,
+            color,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Handle of a light added to a [`LightMap`].
+/// Handles of removed lights stay invalid even when their slot is reused.
+pub struct LightId {
+    index: usize,
+    generation: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Light accumulated from many sources on a grid. Every light keeps its own contribution,
+/// so moving, changing or removing one doesn't recompute the others.
+pub struct LightMap {
+    falloff: Falloff,
+    ambient: Color,
+    light: Grid<Color>,
+    slots: Vec<Slot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Slot {
+    /// Bumped every time the light is removed, so old ids don't match a new light
+    generation: u32,
+    source: Option<Source>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Source {
+    light: Light,
+    contribution: Vec<(Point, Color)>,
+}
+
+impl LightMap {
+    #[must_use]
+    /// Dark map whose lights all fade with the falloff curve
+    pub fn new(width: i32, height: i32, falloff: Falloff) -> Self {
+        LightMap {
+            falloff,
+            ambient: Color::BLACK,
+            light: Grid::new(width, height, Color::BLACK),
+            slots: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    /// Light present on every tile, regardless of walls
+    pub fn with_ambient(mut self, ambient: Color) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    #[must_use]
+    /// Build a map lit by all the lights at once
+    pub fn build<F>(
+        width: i32,
+        height: i32,
+        falloff: Falloff,
+        lights: &[Light],
+        mut is_transparent: F,
+    ) -> Self
+    where
+        F: FnMut(Point) -> bool,
+    {
+        let mut map = LightMap::new(width, height, falloff);
+        for &light in lights {
+            map.add(light, &mut is_transparent);
+        }
+        map
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> i32 {
+        self.light.width()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn height(&self) -> i32 {
+        self.light.height()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn falloff(&self) -> Falloff {
+        self.falloff
+    }
+
+    #[must_use]
+    /// Ambient plus every light reaching the point, black outside the map.
+    /// Negative channels, e.g. from float drift after removing lights, read as zero.
+    pub fn get(&self, point: Point) -> Color {
+        self.light.get(point).map_or(Color::BLACK, |&light| {
+            let total = light + self.ambient;
+            Color::new(total.r.max(0.0), total.g.max(0.0), total.b.max(0.0))
+        })
+    }
+
+    #[must_use]
+    /// Lights only, without the ambient.
+    /// Channels may be slightly negative from float drift after removing lights.
+    pub fn grid(&self) -> &Grid<Color> {
+        &self.light
+    }
+
+    #[must_use]
+    pub fn light(&self, id: LightId) -> Option<&Light> {
+        self.source(id).map(|source| &source.light)
+    }
+
+    /// Iterate over added lights
+    pub fn lights(&self) -> impl Iterator<Item = (LightId, &Light)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let id = LightId {
+                index,
+                generation: slot.generation,
+            };
+            Some((id, &slot.source.as_ref()?.light))
+        })
+    }
+
+    /// Add a light and cast it, tiles outside the map count as opaque
+    pub fn add<F>(&mut self, light: Light, is_transparent: F) -> LightId
+    where
+        F: FnMut(Point) -> bool,
+    {
+        let source = Some(self.cast(light, is_transparent));
+        let index = if let Some(index) = self.slots.iter().position(|slot| slot.source.is_none()) {
+            self.slots[index].source = source;
+            index
+        } else {
+            self.slots.push(Slot {
+                generation: 0,
+                source,
+            });
+            self.slots.len() - 1
+        };
+        LightId {
+            index,
+            generation: self.slots[index].generation,
+        }
+    }
+
+    /// Remove a light and its contribution
+    pub fn remove(&mut self, id: LightId) -> Option<Light> {
+        let source = self.take(id)?;
+        self.subtract(&source);
+        Some(source.light)
+    }
+
+    /// Replace a light, recasting only that one. Returns `false` if the id isn't in the map.
+    pub fn update<F>(&mut self, id: LightId, light: Light, is_transparent: F) -> bool
+    where
+        F: FnMut(Point) -> bool,
+    {
+        let Some(old) = self.take(id) else {
+            return false;
+        };
+        self.subtract(&old);
+        let slot = &mut self.slots[id.index];
+        // Same light changed, so its id stays valid
+        slot.generation = id.generation;
+        slot.source = Some(Self::cast_into(
+            &mut self.light,
+            self.falloff,
+            light,
+            is_transparent,
+        ));
+        true
+    }
+
+    /// Move a light, recasting only that one. Returns `false` if the id isn't in the map.
+    pub fn move_light<F>(&mut self, id: LightId, position: Point, is_transparent: F) -> bool
+    where
+        F: FnMut(Point) -> bool,
+    {
+        match self.light(id) {
+            Some(&light) => self.update(id, Light { position, ..light }, is_transparent),
+            None => false,
+        }
+    }
+
+    /// Recast every light, e.g. after walls changed
+    pub fn rebuild<F>(&mut self, mut is_transparent: F)
+    where
+        F: FnMut(Point) -> bool,
+    {
+        self.light.fill(Color::BLACK);
+        for slot in &mut self.slots {
+            if let Some(source) = slot.source.take() {
+                slot.source = Some(Self::cast_into(
+                    &mut self.light,
+                    self.falloff,
+                    source.light,
+                    &mut is_transparent,
+                ));
+            }
+        }
+    }
+
+    fn source(&self, id: LightId) -> Option<&Source> {
+        self.slots
+            .get(id.index)
+            .filter(|slot| slot.generation == id.generation)?
+            .source
+            .as_ref()
+    }
+
+    /// Take the light out of its slot, invalidating its id
+    fn take(&mut self, id: LightId) -> Option<Source> {
+        let slot = self
+            .slots
+            .get_mut(id.index)
+            .filter(|slot| slot.generation == id.generation)?;
+        let source = slot.source.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        Some(source)
+    }
+
+    fn cast<F>(&mut self, light: Light, is_transparent: F) -> Source
+    where
+        F: FnMut(Point) -> bool,
+    {
+        Self::cast_into(&mut self.light, self.falloff, light, is_transparent)
+    }
+
+    fn cast_into<F>(
+        grid: &mut Grid<Color>,
+        falloff: Falloff,
+        light: Light,
+        mut is_transparent: F,
+    ) -> Source
+    where
+        F: FnMut(Point) -> bool,
+    {
+        let mut contribution = Vec::new();
+        if grid.contains(light.position) {
+            let visible = field_of_view(light.position, light.radius, |point| {
+                grid.contains(point) && is_transparent(point)
+            });
+            for point in visible {
+                if !grid.contains(point) {
+                    continue;
+                }
+                let distance = point.distance_to(light.position);
+                let color =
+                    light.color * (light.intensity * falloff.factor(distance, light.radius));
+                grid[point] += color;
+                contribution.push((point, color));
+            }
+        }
+        Source {
+            light,
+            contribution,
+        }
+    }
+
+    fn subtract(&mut self, source: &Source) {
+        for &(point, color) in &source.contribution {
+            self.light[point] -= color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, Falloff, Light, LightMap, Point};
+
+    #[test]
+    fn falloff_curves() {
+        for falloff in [
+            Falloff::Linear,
+            Falloff::Quadratic,
+            Falloff::Smooth,
+            Falloff::InverseSquare,
+        ] {
+            assert!((falloff.factor(0.0, 4) - 1.0).abs() < 1e-6);
+            assert!(falloff.factor(2.0, 4) > falloff.factor(3.0, 4));
+            assert!(falloff.factor(4.0, 4) > 0.0);
+        }
+        assert!((Falloff::Constant.factor(4.0, 4) - 1.0).abs() < 1e-6);
+        assert!((Falloff::Linear.factor(2.5, 4) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn walls_and_colours() {
+        // Wall column at x = 5
+        let open = |p: Point| p.x != 5;
+        let red = Light::new(Point::new(2, 2), 6, 1.0, Color::new(1.0, 0.0, 0.0));
+        let blue = Light::new(Point::new(3, 2), 6, 0.5, Color::new(0.0, 0.0, 1.0));
+        let map = LightMap::build(10, 5, Falloff::Constant, &[red, blue], open);
+        assert_eq!(Color::new(1.0, 0.0, 0.5), map.get(Point::new(2, 2)));
+        // The wall is lit, what's behind it isn't
+        assert_eq!(Color::new(1.0, 0.0, 0.5), map.get(Point::new(5, 2)));
+        assert_eq!(Color::BLACK, map.get(Point::new(6, 2)));
+        assert_eq!(Color::BLACK, map.get(Point::new(-1, 2)));
+
+        let ambient = map.clone().with_ambient(Color::new(0.1, 0.1, 0.1));
+        assert_eq!(Color::new(0.1, 0.1, 0.1), ambient.get(Point::new(6, 2)));
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let open = |p: Point| p != Point::new(4, 4);
+        let lights = [
+            Light::new(Point::new(1, 1), 4, 1.0, Color::WHITE),
+            Light::new(Point::new(6, 6), 3, 0.8, Color::new(1.0, 0.5, 0.0)),
+        ];
+        let mut map = LightMap::new(8, 8, Falloff::Quadratic);
+        let first = map.add(lights[0], open);
+        let second = map.add(lights[1], open);
+
+        assert!(map.move_light(first, Point::new(6, 1), open));
+        let moved = [
+            Light::new(Point::new(6, 1), 4, 1.0, Color::WHITE),
+            lights[1],
+        ];
+        let expected = LightMap::build(8, 8, Falloff::Quadratic, &moved, open);
+        for (point, color) in expected.grid().iter() {
+            let actual = map.get(point);
+            assert!((actual.r - color.r).abs() < 1e-5, "{point:?}");
+            assert!((actual.b - color.b).abs() < 1e-5, "{point:?}");
+        }
+
+        assert_eq!(Some(lights[1]), map.remove(second));
+        assert_eq!(None, map.remove(second));
+        assert!(!map.move_light(second, Point::new(0, 0), open));
+        assert_eq!(Color::BLACK, map.get(Point::new(6, 7)));
+        assert_eq!(1, map.lights().count());
+
+        // The freed slot is reused, but the old id doesn't reach the new light
+        let third = map.add(lights[1], open);
+        assert_ne!(second, third);
+        assert_eq!(None, map.light(second));
+        assert_eq!(Some(&lights[1]), map.light(third));
+    }
+
+    #[test]
+    fn add_then_remove_restores() {
+        let open = |_| true;
+        let mut map = LightMap::new(5, 5, Falloff::Linear);
+        let dark = map.add(Light::new(Point::new(2, 2), 3, -1.0, Color::WHITE), open);
+        let bright = map.add(Light::new(Point::new(1, 2), 3, 2.0, Color::WHITE), open);
+        let before = map.grid().clone();
+        let extra = map.add(Light::new(Point::new(3, 3), 2, 0.7, Color::WHITE), open);
+        map.remove(extra);
+        for (point, color) in before.iter() {
+            assert!((map.grid()[point].r - color.r).abs() < 1e-5, "{point:?}");
+        }
+        // A negative light darkens others but never reads below black
+        map.remove(bright);
+        assert_eq!(Color::BLACK, map.get(Point::new(2, 2)));
+        assert!(map.light(dark).is_some());
+    }
+}
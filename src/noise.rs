@@ -0,0 +1,459 @@
+//! Seeded coherent noise generators.
+//!
+//! Every generator uses only integer hashing and basic floating point arithmetic,
+//! so the same seed produces the same values on every platform.
+
+use super::{Point, Vec2};
+
+/// Skew from the input space to the `OpenSimplex2` lattice, `(sqrt(3) - 1) / 2`
+const SKEW: f32 = 0.366_025_4;
+/// Unskew from the `OpenSimplex2` lattice back to the input space, `(1 / sqrt(3) - 1) / 2`
+const UNSKEW: f32 = -0.211_324_87;
+/// Squared radius of the area every `OpenSimplex2S` lattice point contributes to
+const RADIUS_SQUARED: f32 = 2.0 / 3.0;
+/// Scale of the raw `OpenSimplex2S` sum from the reference implementation,
+/// dividing by it brings the sum into `-1.0..=1.0`
+const OPEN_SIMPLEX_NORMALIZER: f32 = 0.054_818_665;
+
+/// First six of the 24 `OpenSimplex2S` gradients at `7.5° + k * 15°`,
+/// the others are these rotated by quarter turns
+const OPEN_SIMPLEX_GRADIENTS: [(f32, f32); 6] = [
+    (0.991_444_9, 0.130_526_19),
+    (0.923_879_5, 0.382_683_43),
+    (0.793_353_3, 0.608_761_4),
+    (0.608_761_4, 0.793_353_3),
+    (0.382_683_43, 0.923_879_5),
+    (0.130_526_19, 0.991_444_9),
+];
+
+/// Gradients for Perlin noise, all of length 1
+const GRADIENTS: [(f32, f32); 8] = [
+    (1.0, 0.0),
+    (
+        std::f32::consts::FRAC_1_SQRT_2,
+        std::f32::consts::FRAC_1_SQRT_2,
+    ),
+    (0.0, 1.0),
+    (
+        -std::f32::consts::FRAC_1_SQRT_2,
+        std::f32::consts::FRAC_1_SQRT_2,
+    ),
+    (-1.0, 0.0),
+    (
+        -std::f32::consts::FRAC_1_SQRT_2,
+        -std::f32::consts::FRAC_1_SQRT_2,
+    ),
+    (0.0, -1.0),
+    (
+        std::f32::consts::FRAC_1_SQRT_2,
+        -std::f32::consts::FRAC_1_SQRT_2,
+    ),
+];
+
+/// Two-dimensional noise function returning values in `-1.0..=1.0`
+pub trait Noise {
+    /// Noise value at the position
+    fn sample(&self, position: Vec2) -> f32;
+
+    /// Noise value at the tile, coordinates are multiplied by `frequency` first
+    fn sample_point(&self, point: Point, frequency: f32) -> f32 {
+        self.sample(Vec2::from(point) * frequency)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Random values at integer lattice points, smoothly interpolated between them.
+pub struct ValueNoise {
+    pub seed: u32,
+}
+
+impl ValueNoise {
+    #[must_use]
+    pub const fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+
+    fn lattice(self, x: i32, y: i32) -> f32 {
+        unit(hash(self.seed, x, y))
+    }
+}
+
+impl Noise for ValueNoise {
+    fn sample(&self, position: Vec2) -> f32 {
+        let (x0, y0, fx, fy) = split(position);
+        let u = fade(fx);
+        let v = fade(fy);
+        let top = lerp(self.lattice(x0, y0), self.lattice(x0 + 1, y0), u);
+        let bottom = lerp(self.lattice(x0, y0 + 1), self.lattice(x0 + 1, y0 + 1), u);
+        lerp(top, bottom, v)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Classic gradient noise on a square lattice.
+pub struct Perlin {
+    pub seed: u32,
+}
+
+impl Perlin {
+    #[must_use]
+    pub const fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+
+    fn influence(self, x: i32, y: i32, dx: f32, dy: f32) -> f32 {
+        let (gx, gy) = gradient(hash(self.seed, x, y));
+        gx * dx + gy * dy
+    }
+}
+
+impl Noise for Perlin {
+    fn sample(&self, position: Vec2) -> f32 {
+        let (x0, y0, fx, fy) = split(position);
+        let u = fade(fx);
+        let v = fade(fy);
+        let top = lerp(
+            self.influence(x0, y0, fx, fy),
+            self.influence(x0 + 1, y0, fx - 1.0, fy),
+            u,
+        );
+        let bottom = lerp(
+            self.influence(x0, y0 + 1, fx, fy - 1.0),
+            self.influence(x0 + 1, y0 + 1, fx - 1.0, fy - 1.0),
+            u,
+        );
+        // Maximum absolute value of 2D gradient noise is sqrt(0.5)
+        (lerp(top, bottom, v) * std::f32::consts::SQRT_2).clamp(-1.0, 1.0)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// `OpenSimplex2S` gradient noise on a triangular lattice,
+/// with less visible axis-aligned artifacts than Perlin.
+pub struct OpenSimplex {
+    pub seed: u32,
+}
+
+impl OpenSimplex {
+    #[must_use]
+    pub const fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+
+    /// Contribution of the lattice point `(x0 + i, y0 + j)` at offset `(dx, dy)` from `(x0, y0)`
+    #[allow(clippy::cast_precision_loss)]
+    fn vertex(self, x0: i32, y0: i32, i: i32, j: i32, dx: f32, dy: f32) -> f32 {
+        let unskew = (i + j) as f32 * UNSKEW;
+        let dx = dx - (i as f32 + unskew);
+        let dy = dy - (j as f32 + unskew);
+        let falloff = RADIUS_SQUARED - dx * dx - dy * dy;
+        if falloff <= 0.0 {
+            return 0.0;
+        }
+        let (gx, gy) = open_simplex_gradient(hash(self.seed, x0 + i, y0 + j));
+        let falloff = falloff * falloff;
+        falloff * falloff * (gx * dx + gy * dy)
+    }
+}
+
+impl Noise for OpenSimplex {
+    fn sample(&self, position: Vec2) -> f32 {
+        let skew = (position.x + position.y) * SKEW;
+        let (x0, y0, xi, yi) = split(Vec2::new(position.x + skew, position.y + skew));
+        let t = (xi + yi) * UNSKEW;
+        let (dx, dy) = (xi + t, yi + t);
+
+        // The cell's own corner and the opposite one, then the two lattice points
+        // closest to the position out of the four candidates around its triangle
+        let mut sum = self.vertex(x0, y0, 0, 0, dx, dy) + self.vertex(x0, y0, 1, 1, dx, dy);
+        let (xmyi, ymxi) = (xi - yi, yi - xi);
+        let (first, second) = if t < UNSKEW {
+            (
+                if xi + xmyi > 1.0 { (2, 1) } else { (0, 1) },
+                if yi + ymxi > 1.0 { (1, 2) } else { (1, 0) },
+            )
+        } else {
+            (
+                if xi + xmyi < 0.0 { (-1, 0) } else { (1, 0) },
+                if yi + ymxi < 0.0 { (0, -1) } else { (0, 1) },
+            )
+        };
+        for (i, j) in [first, second] {
+            sum += self.vertex(x0, y0, i, j, dx, dy);
+        }
+        // The clamp only catches rounding at the extremes
+        (sum / OPEN_SIMPLEX_NORMALIZER).clamp(-1.0, 1.0)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How many octaves fractal noises sum and how they change from one octave to the next
+pub struct Octaves {
+    pub count: u32,
+    /// Frequency multiplier between octaves
+    pub lacunarity: f32,
+    /// Amplitude multiplier between octaves
+    pub gain: f32,
+}
+
+impl Default for Octaves {
+    /// Four octaves with lacunarity 2 and gain 0.5
+    fn default() -> Self {
+        Self {
+            count: 4,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+}
+
+impl Octaves {
+    #[must_use]
+    /// Four octaves with lacunarity 2 and gain 0.5
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    #[must_use]
+    pub fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    #[must_use]
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Sample position and amplitude of every octave
+    fn layers(self, position: Vec2) -> impl Iterator<Item = (Vec2, f32)> {
+        let mut position = position;
+        let mut amplitude = 1.0;
+        (0..self.count).map(move |octave| {
+            let layer = (position + octave_offset(octave), amplitude);
+            position *= self.lacunarity;
+            amplitude *= self.gain;
+            layer
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Fractal Brownian motion: several octaves of a noise summed with decreasing amplitude.
+pub struct Fbm<N> {
+    pub noise: N,
+    pub octaves: Octaves,
+}
+
+impl<N: Noise> Fbm<N> {
+    #[must_use]
+    /// Default [`Octaves`] of the noise
+    pub fn new(noise: N) -> Self {
+        Self {
+            noise,
+            octaves: Octaves::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_octaves(mut self, octaves: Octaves) -> Self {
+        self.octaves = octaves;
+        self
+    }
+}
+
+impl<N: Noise> Noise for Fbm<N> {
+    fn sample(&self, position: Vec2) -> f32 {
+        let mut sum = 0.0;
+        let mut total_amplitude = 0.0;
+        for (position, amplitude) in self.octaves.layers(position) {
+            sum += self.noise.sample(position) * amplitude;
+            total_amplitude += amplitude;
+        }
+        if total_amplitude > 0.0 {
+            sum / total_amplitude
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Ridged multifractal: like `Fbm`, but every octave is folded with `1 - |n|`,
+/// producing sharp crests useful for mountain ranges and rivers.
+pub struct Ridged<N> {
+    pub noise: N,
+    pub octaves: Octaves,
+}
+
+impl<N: Noise> Ridged<N> {
+    #[must_use]
+    /// Default [`Octaves`] of the noise
+    pub fn new(noise: N) -> Self {
+        Self {
+            noise,
+            octaves: Octaves::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_octaves(mut self, octaves: Octaves) -> Self {
+        self.octaves = octaves;
+        self
+    }
+}
+
+impl<N: Noise> Noise for Ridged<N> {
+    fn sample(&self, position: Vec2) -> f32 {
+        let mut sum = 0.0;
+        let mut total_amplitude = 0.0;
+        let mut weight = 1.0;
+        for (position, amplitude) in self.octaves.layers(position) {
+            let ridge = 1.0 - self.noise.sample(position).abs();
+            let ridge = ridge * ridge * weight;
+            weight = ridge.clamp(0.0, 1.0);
+            sum += ridge * amplitude;
+            total_amplitude += amplitude;
+        }
+        if total_amplitude > 0.0 {
+            sum / total_amplitude * 2.0 - 1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Shift every octave a little so lattice points of different octaves don't line up
+#[allow(clippy::cast_precision_loss)]
+fn octave_offset(octave: u32) -> Vec2 {
+    let octave = octave as f32;
+    Vec2::new(octave * 17.31, octave * 23.89)
+}
+
+/// Integer hash of lattice coordinates mixed with the seed
+#[allow(clippy::cast_sign_loss)]
+fn hash(seed: u32, x: i32, y: i32) -> u32 {
+    let mut h = seed ^ (x as u32).wrapping_mul(0x27d4_eb2d) ^ (y as u32).wrapping_mul(0x1656_67b1);
+    h = (h ^ (h >> 16)).wrapping_mul(0x7feb_352d);
+    h = (h ^ (h >> 15)).wrapping_mul(0x846c_a68b);
+    h ^ (h >> 16)
+}
+
+/// Map a hash to `-1.0..=1.0`
+#[allow(clippy::cast_precision_loss)]
+fn unit(hash: u32) -> f32 {
+    (hash >> 8) as f32 / 8_388_607.5 - 1.0
+}
+
+fn gradient(hash: u32) -> (f32, f32) {
+    GRADIENTS[(hash >> 29) as usize]
+}
+
+fn open_simplex_gradient(hash: u32) -> (f32, f32) {
+    let (x, y) = OPEN_SIMPLEX_GRADIENTS[hash as usize % 6];
+    match (hash >> 8) % 4 {
+        0 => (x, y),
+        1 => (-y, x),
+        2 => (-x, -y),
+        _ => (y, -x),
+    }
+}
+
+/// Integer cell and fractional offset inside it
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_precision_loss)]
+fn split(position: Vec2) -> (i32, i32, f32, f32) {
+    let x = position.x.floor();
+    let y = position.y.floor();
+    (x as i32, y as i32, position.x - x, position.y - y)
+}
+
+/// Quintic smoothstep `6t^5 - 15t^4 + 10t^3`
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fbm, Noise, Octaves, OpenSimplex, Perlin, Point, Ridged, ValueNoise, Vec2};
+
+    fn in_range<N: Noise>(noise: &N) -> bool {
+        (0..40).all(|y| {
+            (0..40).all(|x| (-1.0..=1.0).contains(&noise.sample_point(Point::new(x, y), 0.13)))
+        })
+    }
+
+    #[test]
+    fn deterministic() {
+        let position = Vec2::new(3.7, -12.25);
+        assert_eq!(
+            Perlin::new(42).sample(position),
+            Perlin::new(42).sample(position)
+        );
+        assert_ne!(
+            Perlin::new(42).sample(position),
+            Perlin::new(43).sample(position)
+        );
+        assert_eq!(
+            OpenSimplex::new(7).sample_point(Point::new(3, 5), 0.5),
+            OpenSimplex::new(7).sample(Vec2::new(1.5, 2.5))
+        );
+    }
+
+    #[test]
+    fn golden_values() {
+        // These must stay the same on every platform and between releases
+        let position = Vec2::new(1.25, 2.5);
+        assert!((ValueNoise::new(1).sample(position) - 0.660_146_2).abs() < 1e-6);
+        assert!((Perlin::new(1).sample(position) - 0.037_525_7).abs() < 1e-6);
+        assert!((OpenSimplex::new(1).sample(position) - 0.646_326_7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn open_simplex_spread() {
+        // The normalizer brings the extremes close to -1 and 1 without overshooting much
+        let noise = OpenSimplex::new(9);
+        let (low, high) = (0..200).flat_map(|y| (0..200).map(move |x| (x, y))).fold(
+            (0.0_f32, 0.0_f32),
+            |(low, high), (x, y)| {
+                let value = noise.sample_point(Point::new(x, y), 0.071);
+                (low.min(value), high.max(value))
+            },
+        );
+        assert!(low < -0.7 && high > 0.7);
+    }
+
+    #[test]
+    fn lattice_points() {
+        let perlin = Perlin::new(5);
+        assert!(perlin.sample(Vec2::new(3.0, -4.0)).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn ranges() {
+        assert!(in_range(&ValueNoise::new(1)));
+        assert!(in_range(&Perlin::new(2)));
+        assert!(in_range(&OpenSimplex::new(3)));
+        assert!(in_range(
+            &Fbm::new(Perlin::new(4)).with_octaves(Octaves::new().with_count(6))
+        ));
+        assert!(in_range(&Ridged::new(OpenSimplex::new(5))));
+    }
+}
@@ -0,0 +1,179 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::{Direction, Point, TileRect};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Node {
+    /// Cost so far plus heuristic
+    estimate: f32,
+    point: Point,
+}
+
+impl Eq for Node {}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so `BinaryHeap` pops the lowest estimate first
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+/// Find the cheapest path from `start` to `goal` moving in the given directions,
+/// never leaving `bounds`, e.g. the map.
+/// `cost` returns the price of entering a tile or `None` if it can't be entered;
+/// prices should be at least 1 so the distance heuristic doesn't overestimate.
+/// The returned path includes both `start` and `goal`.
+pub fn astar<F>(
+    start: Point,
+    goal: Point,
+    directions: &[Direction],
+    bounds: TileRect,
+    mut cost: F,
+) -> Option<Vec<Point>>
+where
+    F: FnMut(Point) -> Option<f32>,
+{
+    let diagonal = directions.iter().any(|dir| dir.is_diagonal());
+    astar_with(
+        start,
+        goal,
+        |point| {
+            directions
+                .iter()
+                .map(|&dir| point + dir)
+                .filter(|&next| bounds.contains(next))
+                .filter_map(|next| cost(next).map(|step| (next, step)))
+                .collect::<Vec<_>>()
+        },
+        |point| {
+            if diagonal {
+                point.chebyshev_distance_to(goal) as f32
+            } else {
+                point.manhattan_distance_to(goal) as f32
+            }
+        },
+        // Every point of the bounds and the start, which may lie outside them
+        bounds.area().saturating_add(1),
+    )
+}
+
+#[must_use]
+/// Generic A*: `neighbours` lists tiles reachable from a point with the price of each step,
+/// `heuristic` estimates the remaining price to the goal and must never overestimate it.
+/// Useful for maps with unusual topology, like portals or wrapping edges.
+/// Gives up and returns `None` once more than `max_visited` points were reached.
+pub fn astar_with<N, I, H>(
+    start: Point,
+    goal: Point,
+    mut neighbours: N,
+    heuristic: H,
+    max_visited: usize,
+) -> Option<Vec<Point>>
+where
+    N: FnMut(Point) -> I,
+    I: IntoIterator<Item = (Point, f32)>,
+    H: Fn(Point) -> f32,
+{
+    let mut open = BinaryHeap::new();
+    // Best known cost and previous tile for every visited point
+    let mut visited: HashMap<Point, (f32, Point)> = HashMap::new();
+    visited.insert(start, (0.0, start));
+    open.push(Node {
+        estimate: heuristic(start),
+        point: start,
+    });
+
+    while let Some(Node { estimate, point }) = open.pop() {
+        if point == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while current != start {
+                current = visited[&current].1;
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let spent = visited[&point].0;
+        if estimate > spent + heuristic(point) {
+            // Stale entry, a cheaper way to this point was already found
+            continue;
+        }
+
+        for (next, step) in neighbours(point) {
+            let total = spent + step;
+            if visited.get(&next).map_or(true, |&(known, _)| total < known) {
+                visited.insert(next, (total, point));
+                if visited.len() > max_visited {
+                    return None;
+                }
+                open.push(Node {
+                    estimate: total + heuristic(next),
+                    point: next,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{astar, astar_with, Point, TileRect};
+    use crate::{DIR4, DIR8};
+
+    #[test]
+    fn open_field() {
+        let bounds = TileRect::new(-5, -5, 10, 10);
+        let path = astar(Point::new(0, 0), Point::new(3, 3), &DIR8, bounds, |_| {
+            Some(1.0)
+        })
+        .unwrap();
+        assert_eq!(path, [(0, 0), (1, 1), (2, 2), (3, 3)]);
+        let path = astar(Point::new(0, 0), Point::new(3, 3), &DIR4, bounds, |_| {
+            Some(1.0)
+        })
+        .unwrap();
+        assert_eq!(7, path.len());
+    }
+
+    #[test]
+    fn around_wall() {
+        // Wall at x = 2 from y = -5 to y = 5
+        let bounds = TileRect::new(-10, -10, 21, 21);
+        let cost = |p: Point| (p.x != 2 || p.y.abs() > 5).then_some(1.0);
+        let path = astar(Point::new(0, 0), Point::new(4, 0), &DIR4, bounds, cost).unwrap();
+        assert_eq!(Some(&Point::new(4, 0)), path.last());
+        assert!(path.iter().all(|p| p.x != 2 || p.y.abs() > 5));
+        assert_eq!(17, path.len());
+    }
+
+    #[test]
+    fn unreachable() {
+        let bounds = TileRect::new(-3, -3, 7, 7);
+        let cost = |p: Point| (p.x != 1).then_some(1.0);
+        assert!(astar(Point::new(0, 0), Point::new(2, 0), &DIR8, bounds, cost).is_none());
+
+        // Endless plane with the goal walled in: only the limit stops the search
+        let neighbours = |p: Point| {
+            DIR4.into_iter()
+                .map(move |dir| p + dir)
+                .filter(|&next| next.x != 100)
+                .map(|next| (next, 1.0))
+        };
+        let goal = Point::new(200, 0);
+        let heuristic = |p: Point| p.manhattan_distance_to(goal) as f32;
+        assert!(astar_with(Point::new(0, 0), goal, neighbours, heuristic, 10_000).is_none());
+    }
+}
@@ -1,7 +1,7 @@
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use vek::num_traits::Zero;
 
-use super::{Direction, Vec2};
+use super::{Direction, LineOfSight, Vec2};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -90,6 +90,20 @@ impl Point {
         (self.square_distance_to(other) as f32).sqrt()
     }
 
+    #[inline]
+    #[must_use]
+    /// Manhattan (taxicab) distance to other point
+    pub fn manhattan_distance_to(self, other: Self) -> u32 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Chebyshev (king's move) distance to other point
+    pub fn chebyshev_distance_to(self, other: Self) -> u32 {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+
     #[must_use]
     /// Points between self and other
     pub fn line_to(self, other: Point) -> Vec<Point> {
@@ -97,6 +111,36 @@ impl Point {
             .map(Self::from)
             .collect()
     }
+
+    /// Check if nothing blocks the Bresenham line to the target.
+    /// Only tiles between the two points are passed to `is_transparent`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first blocking tile on the way
+    pub fn has_line_of_sight<F>(self, target: Point, is_transparent: F) -> Result<(), Point>
+    where
+        F: FnMut(Point) -> bool,
+    {
+        LineOfSight::default().check(self, target, is_transparent)
+    }
+
+    /// Check if nothing blocks the line to the target, drawn with selected algorithm
+    ///
+    /// # Errors
+    ///
+    /// Returns the first blocking tile on the way, see [`LineOfSight::check`]
+    pub fn has_line_of_sight_with<F>(
+        self,
+        target: Point,
+        sight: LineOfSight,
+        is_transparent: F,
+    ) -> Result<(), Point>
+    where
+        F: FnMut(Point) -> bool,
+    {
+        sight.check(self, target, is_transparent)
+    }
 }
 
 impl Default for Point {
@@ -505,6 +549,8 @@ mod tests {
         let pt2 = Point::new(4, 6);
         assert_eq!(25, pt.square_distance_to(pt2));
         assert!(f32::abs(pt.distance_to(pt2) - 5.0) < f32::EPSILON);
+        assert_eq!(7, pt.manhattan_distance_to(pt2));
+        assert_eq!(4, pt.chebyshev_distance_to(pt2));
     }
 
     #[test]
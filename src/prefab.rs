@@ -0,0 +1,268 @@
+//! Prefabricated rooms ("vaults") authored as ASCII art and stamped into a `Grid`
+//! at any position and in any of the eight `Transform` orientations.
+
+use super::{cp437, Grid, Point, TileRect, Transform};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StampError {
+    /// Tile of the prefab would land outside of the grid
+    OutOfBounds(Point),
+    /// Tile of the prefab would overwrite a tile that may not be overwritten
+    Overlap(Point),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Template of tiles inside a `width` x `height` box.
+/// "Don't care" cells have no tile and leave the target untouched when stamped.
+pub struct Prefab<T> {
+    width: i32,
+    height: i32,
+    tiles: Vec<(Point, T)>,
+}
+
+impl Prefab<char> {
+    #[must_use]
+    /// Parse a template, characters in `dont_care` are skipped
+    pub fn parse(template: &str, dont_care: &[char]) -> Self {
+        Self::parse_with(template, |c| (!dont_care.contains(&c)).then_some(c))
+    }
+}
+
+impl Prefab<u8> {
+    #[must_use]
+    /// Parse a template into CP437 bytes, characters in `dont_care` are skipped
+    pub fn parse_cp437(template: &str, dont_care: &[char]) -> Self {
+        Self::parse_with(template, |c| {
+            (!dont_care.contains(&c)).then(|| cp437::to_byte(c))
+        })
+    }
+}
+
+impl<T> Prefab<T> {
+    #[must_use]
+    /// Parse a template, converting every character with `tile`, which returns `None` for "don't care".
+    /// Blank lines at the start and end are ignored so templates can be written as raw string literals.
+    /// Lines shorter than the widest one are padded with "don't care".
+    pub fn parse_with<F>(template: &str, mut tile: F) -> Self
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let lines: Vec<&str> = template.lines().collect();
+        let first = lines.iter().position(|line| !line.trim().is_empty());
+        let last = lines.iter().rposition(|line| !line.trim().is_empty());
+        let lines = match (first, last) {
+            (Some(first), Some(last)) => &lines[first..=last],
+            _ => &[][..],
+        };
+
+        let mut width = 0;
+        let mut tiles = Vec::new();
+        for (y, line) in (0..).zip(lines) {
+            for (x, c) in (0..).zip(line.chars()) {
+                width = width.max(x + 1);
+                if let Some(value) = tile(c) {
+                    tiles.push((Point::new(x, y), value));
+                }
+            }
+        }
+        Prefab {
+            width,
+            height: (0..).zip(lines).last().map_or(0, |(y, _)| y + 1),
+            tiles,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    #[inline]
+    #[must_use]
+    /// Tiles that aren't "don't care", with their positions inside the template
+    pub fn tiles(&self) -> &[(Point, T)] {
+        &self.tiles
+    }
+
+    #[must_use]
+    /// Create a prefab of the same shape by converting every tile
+    pub fn map<U, F>(&self, mut f: F) -> Prefab<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Prefab {
+            width: self.width,
+            height: self.height,
+            tiles: self
+                .tiles
+                .iter()
+                .map(|(point, value)| (*point, f(value)))
+                .collect(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Area covered when the top-left corner of the transformed prefab is at `position`
+    pub fn bounds(&self, position: Point, transform: Transform) -> TileRect {
+        let (width, height) = transform.size(self.width, self.height);
+        TileRect::new(position.x, position.y, width, height)
+    }
+
+    /// Tiles with the points they land on when the top-left corner
+    /// of the transformed prefab is at `position`
+    pub fn placed(
+        &self,
+        position: Point,
+        transform: Transform,
+    ) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.tiles.iter().map(move |(point, value)| {
+            (
+                position + transform.apply_in(*point, self.width, self.height),
+                value,
+            )
+        })
+    }
+
+    #[must_use]
+    /// First point where a placed tile hits an occupied one
+    pub fn overlap<F>(
+        &self,
+        position: Point,
+        transform: Transform,
+        mut occupied: F,
+    ) -> Option<Point>
+    where
+        F: FnMut(Point) -> bool,
+    {
+        self.placed(position, transform)
+            .map(|(point, _)| point)
+            .find(|&point| occupied(point))
+    }
+
+    /// Copy tiles into the grid. Nothing is written if any of them would land outside of it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StampError::OutOfBounds` with the first tile outside of the grid
+    pub fn stamp(
+        &self,
+        grid: &mut Grid<T>,
+        position: Point,
+        transform: Transform,
+    ) -> Result<(), StampError>
+    where
+        T: Clone,
+    {
+        self.stamp_checked(grid, position, transform, |_, _| true)
+    }
+
+    /// Copy tiles into the grid, but only if `can_overwrite` allows replacing every tile they land on.
+    /// Nothing is written if any tile is rejected.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StampError::OutOfBounds` with the first tile outside of the grid,
+    /// or `StampError::Overlap` with the first tile that can't be overwritten
+    pub fn stamp_checked<F>(
+        &self,
+        grid: &mut Grid<T>,
+        position: Point,
+        transform: Transform,
+        mut can_overwrite: F,
+    ) -> Result<(), StampError>
+    where
+        T: Clone,
+        F: FnMut(Point, &T) -> bool,
+    {
+        for (point, _) in self.placed(position, transform) {
+            match grid.get(point) {
+                None => return Err(StampError::OutOfBounds(point)),
+                Some(existing) if !can_overwrite(point, existing) => {
+                    return Err(StampError::Overlap(point))
+                }
+                Some(_) => {}
+            }
+        }
+        for (point, value) in self.placed(position, transform) {
+            grid[point] = value.clone();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, Point, Prefab, StampError, TileRect, Transform};
+
+    const VAULT: &str = r"
+###
+#.+
+#?
+";
+
+    #[test]
+    fn parsing() {
+        let prefab = Prefab::parse(VAULT, &['?']);
+        assert_eq!((3, 3), (prefab.width(), prefab.height()));
+        assert_eq!(7, prefab.tiles().len());
+        assert!(prefab.tiles().contains(&(Point::new(2, 1), '+')));
+
+        let bytes = Prefab::parse_cp437("░▒\n▓█", &[]);
+        assert_eq!(
+            bytes.tiles().iter().map(|(_, b)| *b).collect::<Vec<_>>(),
+            [176, 177, 178, 219]
+        );
+
+        let walls = Prefab::parse_with(VAULT, |c| (c == '#').then_some(true));
+        assert_eq!(5, walls.tiles().len());
+        assert_eq!(0, Prefab::parse("\n  \n", &[]).height());
+    }
+
+    #[test]
+    fn stamping() {
+        let prefab = Prefab::parse(VAULT, &['?']);
+        let mut map = Grid::new(6, 5, ' ');
+        assert_eq!(
+            Ok(()),
+            prefab.stamp(&mut map, Point::new(1, 1), Transform::Rotate90)
+        );
+        assert_eq!(
+            TileRect::new(1, 1, 3, 3),
+            prefab.bounds(Point::new(1, 1), Transform::Rotate90)
+        );
+        // The door moved from the east to the south side, the "don't care" cell stays blank
+        assert_eq!('+', map[Point::new(2, 3)]);
+        assert_eq!(' ', map[Point::new(1, 2)]);
+        assert_eq!(' ', map[Point::new(1, 3)]);
+        assert_eq!('#', map[Point::new(3, 3)]);
+
+        let before = map.clone();
+        assert_eq!(
+            Err(StampError::OutOfBounds(Point::new(6, 2))),
+            prefab.stamp(&mut map, Point::new(4, 2), Transform::Identity)
+        );
+        assert_eq!(before, map);
+
+        let result = prefab.stamp_checked(
+            &mut map,
+            Point::new(3, 0),
+            Transform::Identity,
+            |_, &tile| tile == ' ',
+        );
+        assert_eq!(Err(StampError::Overlap(Point::new(3, 1))), result);
+        assert_eq!(before, map);
+        assert_eq!(
+            Some(Point::new(3, 1)),
+            prefab.overlap(Point::new(3, 0), Transform::Identity, |p| map[p] != ' ')
+        );
+    }
+}
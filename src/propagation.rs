@@ -0,0 +1,306 @@
+//! Fields spreading over tiles around walls: sound heard from a source and scent trails
+//! diffusing and fading over time, e.g. for stealth AI.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::{Direction, Grid, Point, DIR4, DIR8};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Node {
+    intensity: f32,
+    point: Point,
+}
+
+impl Eq for Node {}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` pops the loudest tile first
+        self.intensity.total_cmp(&other.intensity)
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Noise made at a point, flowing around walls along the loudest route
+pub struct Sound {
+    pub source: Point,
+    /// Intensity at the source
+    pub volume: f32,
+    /// Intensity lost per orthogonal step, diagonal steps lose `√2` times as much.
+    /// Negative values count as no loss.
+    pub attenuation: f32,
+}
+
+impl Sound {
+    #[must_use]
+    pub const fn new(source: Point, volume: f32) -> Self {
+        Sound {
+            source,
+            volume,
+            attenuation: 1.0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_attenuation(mut self, attenuation: f32) -> Self {
+        self.attenuation = attenuation.max(0.0);
+        self
+    }
+
+    #[must_use]
+    /// Intensity on every tile of a `width` x `height` area, `0.0` where the sound doesn't reach.
+    /// `cost` returns the extra loss of entering a tile, like a door or water muffling it,
+    /// or `None` if sound can't pass through it at all.
+    pub fn propagate<F>(self, width: i32, height: i32, mut cost: F) -> Grid<f32>
+    where
+        F: FnMut(Point) -> Option<f32>,
+    {
+        let mut heard = Grid::new(width, height, 0.0_f32);
+        if !heard.contains(self.source) || self.volume <= 0.0 {
+            return heard;
+        }
+        heard[self.source] = self.volume;
+        let mut open = BinaryHeap::new();
+        open.push(Node {
+            intensity: self.volume,
+            point: self.source,
+        });
+        while let Some(Node { intensity, point }) = open.pop() {
+            if intensity < heard[point] {
+                continue;
+            }
+            for dir in DIR8 {
+                let next = point + dir;
+                if !heard.contains(next) {
+                    continue;
+                }
+                let step = if dir.is_diagonal() {
+                    std::f32::consts::SQRT_2
+                } else {
+                    1.0
+                };
+                let Some(extra) = cost(next) else {
+                    continue;
+                };
+                let louder = intensity - self.attenuation.max(0.0) * step - extra.max(0.0);
+                if louder > heard[next] {
+                    heard[next] = louder;
+                    open.push(Node {
+                        intensity: louder,
+                        point: next,
+                    });
+                }
+            }
+        }
+        heard
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawScentMap"))]
+/// Scent left on tiles, spreading to open neighbours and fading every [`tick`](ScentMap::tick)
+pub struct ScentMap {
+    scent: Grid<f32>,
+    diffusion: f32,
+    decay: f32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+/// Deserialized scent map before its rates are checked
+struct RawScentMap {
+    scent: Grid<f32>,
+    diffusion: f32,
+    decay: f32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawScentMap> for ScentMap {
+    type Error = &'static str;
+
+    fn try_from(raw: RawScentMap) -> Result<Self, Self::Error> {
+        if !(0.0..=1.0).contains(&raw.diffusion) || !(0.0..=1.0).contains(&raw.decay) {
+            return Err("scent rates are out of range");
+        }
+        Ok(ScentMap {
+            scent: raw.scent,
+            diffusion: raw.diffusion,
+            decay: raw.decay,
+        })
+    }
+}
+
+impl ScentMap {
+    #[must_use]
+    pub fn new(width: i32, height: i32) -> Self {
+        ScentMap {
+            scent: Grid::new(width, height, 0.0),
+            diffusion: 0.25,
+            decay: 0.05,
+        }
+    }
+
+    #[must_use]
+    /// How fast scent evens out between open orthogonal neighbours, limited to `0.0..=1.0`.
+    /// Spreading keeps the total, only decay lowers it.
+    pub fn with_diffusion(mut self, diffusion: f32) -> Self {
+        self.diffusion = diffusion.clamp(0.0, 1.0);
+        self
+    }
+
+    #[must_use]
+    /// Share of the scent lost per tick, limited to `0.0..=1.0`
+    pub fn with_decay(mut self, decay: f32) -> Self {
+        self.decay = decay.clamp(0.0, 1.0);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn diffusion(&self) -> f32 {
+        self.diffusion
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn decay(&self) -> f32 {
+        self.decay
+    }
+
+    #[must_use]
+    /// Scent at the point, `0.0` outside the map
+    pub fn get(&self, point: Point) -> f32 {
+        self.scent.get(point).copied().unwrap_or(0.0)
+    }
+
+    #[must_use]
+    pub fn grid(&self) -> &Grid<f32> {
+        &self.scent
+    }
+
+    /// Leave scent on a tile, ignored outside the map
+    pub fn deposit(&mut self, point: Point, amount: f32) {
+        if let Some(scent) = self.scent.get_mut(point) {
+            *scent += amount;
+        }
+    }
+
+    /// Erase all scent, e.g. after rain
+    pub fn clear(&mut self) {
+        self.scent.fill(0.0);
+    }
+
+    /// Spread scent between open tiles, then let it decay. Closed tiles lose their scent.
+    pub fn tick<F>(&mut self, mut is_open: F)
+    where
+        F: FnMut(Point) -> bool,
+    {
+        let open = Grid::from_fn(self.scent.width(), self.scent.height(), &mut is_open);
+        let keep = 1.0 - self.decay;
+        self.scent = Grid::from_fn(self.scent.width(), self.scent.height(), |point| {
+            if !open[point] {
+                return 0.0;
+            }
+            let here = self.scent[point];
+            // Symmetric exchange with each neighbour, so spreading doesn't create scent
+            let exchange: f32 = DIR4
+                .into_iter()
+                .map(|dir| point + dir)
+                .filter(|&next| open.get(next).copied().unwrap_or(false))
+                .map(|next| self.scent[next] - here)
+                .sum();
+            let spread = here + self.diffusion / 4.0 * exchange;
+            spread * keep
+        });
+    }
+
+    #[must_use]
+    /// Neighbouring tile with the strongest scent, if it's stronger than the point itself.
+    /// Following it leads a tracker towards fresher scent.
+    pub fn strongest_neighbour(&self, point: Point) -> Option<Direction> {
+        let mut best = (self.get(point), None);
+        for dir in DIR8 {
+            let scent = self.get(point + dir);
+            if scent > best.0 {
+                best = (scent, Some(dir));
+            }
+        }
+        best.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Point, ScentMap, Sound};
+    use crate::Direction;
+
+    #[test]
+    fn sound_around_walls() {
+        // Wall at x = 3 with a gap at the bottom row
+        let cost = |p: Point| {
+            if p.x == 3 && p.y < 4 {
+                None
+            } else if p == Point::new(1, 0) {
+                Some(5.0)
+            } else {
+                Some(0.0)
+            }
+        };
+        let heard = Sound::new(Point::new(1, 1), 10.0).propagate(7, 5, cost);
+        assert!((heard[Point::new(1, 1)] - 10.0).abs() < 1e-6);
+        assert!((heard[Point::new(2, 1)] - 9.0).abs() < 1e-6);
+        // Muffled tile
+        assert!((heard[Point::new(1, 0)] - 4.0).abs() < 1e-6);
+        assert!(heard[Point::new(3, 1)].abs() < 1e-6);
+        // Around the wall through the gap at (3, 4)
+        let around = heard[Point::new(4, 1)];
+        assert!(around > 0.0 && around < heard[Point::new(4, 4)]);
+
+        let quiet = Sound::new(Point::new(0, 0), 2.0).with_attenuation(1.5);
+        let heard = quiet.propagate(5, 5, |_| Some(0.0));
+        assert!(heard[Point::new(1, 0)] > 0.0);
+        assert!(heard[Point::new(2, 0)].abs() < 1e-6);
+
+        // Negative attenuation would make neighbours raise each other forever
+        let loud = Sound::new(Point::new(0, 0), 2.0).with_attenuation(-1.0);
+        assert!(loud.attenuation.abs() < 1e-6);
+        let heard = loud.propagate(3, 3, |_| Some(1.0));
+        assert!(heard[Point::new(2, 2)].abs() < 1e-6);
+    }
+
+    #[test]
+    fn scent_diffuses_and_decays() {
+        let mut scent = ScentMap::new(5, 3).with_diffusion(0.5).with_decay(0.1);
+        assert!((ScentMap::new(1, 1).with_diffusion(3.0).diffusion() - 1.0).abs() < 1e-6);
+        scent.deposit(Point::new(1, 1), 10.0);
+        scent.deposit(Point::new(9, 9), 10.0);
+        // Wall column at x = 2
+        let open = |p: Point| p.x != 2;
+        scent.tick(open);
+        assert!(scent.get(Point::new(1, 1)) < 10.0);
+        assert!(scent.get(Point::new(0, 1)) > 0.0);
+        assert!(scent.get(Point::new(3, 1)).abs() < 1e-6);
+        let total: f32 = scent.grid().cells().iter().sum();
+        assert!(total < 10.0);
+
+        assert_eq!(
+            Some(Direction::East),
+            scent.strongest_neighbour(Point::new(0, 1))
+        );
+        assert_eq!(None, scent.strongest_neighbour(Point::new(1, 1)));
+
+        for _ in 0..200 {
+            scent.tick(open);
+        }
+        assert!(scent.get(Point::new(1, 1)) < 1e-3);
+    }
+}
@@ -0,0 +1,369 @@
+use super::{Distance, Point, TileRect};
+
+/// Default number of items a leaf holds before it's split
+const DEFAULT_NODE_CAPACITY: usize = 8;
+
+#[derive(Clone, Debug)]
+enum Node<T> {
+    Leaf(Vec<(TileRect, T)>),
+    Branch {
+        /// Items which don't fit entirely into any of the children
+        items: Vec<(TileRect, T)>,
+        children: Box<[(TileRect, Node<T>); 4]>,
+        len: usize,
+    },
+}
+
+impl<T> Node<T> {
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(items) => items.len(),
+            Node::Branch { len, .. } => *len,
+        }
+    }
+
+    fn insert(&mut self, bounds: TileRect, area: TileRect, value: T, capacity: usize) {
+        match self {
+            Node::Leaf(items) => {
+                items.push((area, value));
+                if items.len() > capacity && (bounds.width > 1 || bounds.height > 1) {
+                    self.split(bounds, capacity);
+                }
+            }
+            Node::Branch {
+                items,
+                children,
+                len,
+            } => {
+                *len += 1;
+                match children
+                    .iter_mut()
+                    .find(|(child_bounds, _)| child_bounds.contains_rect(area))
+                {
+                    Some((child_bounds, child)) => {
+                        child.insert(*child_bounds, area, value, capacity);
+                    }
+                    None => items.push((area, value)),
+                }
+            }
+        }
+    }
+
+    fn split(&mut self, bounds: TileRect, capacity: usize) {
+        let Node::Leaf(items) = std::mem::replace(self, Node::Leaf(Vec::new())) else {
+            return;
+        };
+        let (half_width, half_height) = (bounds.width / 2, bounds.height / 2);
+        let quadrant = |dx: i32, dy: i32, width: i32, height: i32| {
+            (
+                TileRect::new(bounds.x + dx, bounds.y + dy, width, height),
+                Node::Leaf(Vec::new()),
+            )
+        };
+        *self = Node::Branch {
+            items: Vec::new(),
+            children: Box::new([
+                quadrant(0, 0, half_width, half_height),
+                quadrant(half_width, 0, bounds.width - half_width, half_height),
+                quadrant(0, half_height, half_width, bounds.height - half_height),
+                quadrant(
+                    half_width,
+                    half_height,
+                    bounds.width - half_width,
+                    bounds.height - half_height,
+                ),
+            ]),
+            len: 0,
+        };
+        for (area, value) in items {
+            self.insert(bounds, area, value, capacity);
+        }
+    }
+
+    fn remove(&mut self, area: TileRect, value: &T, capacity: usize) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let removed = match self {
+            Node::Leaf(items) => take(items, area, value),
+            Node::Branch {
+                items,
+                children,
+                len,
+            } => {
+                let removed = match children
+                    .iter_mut()
+                    .find(|(child_bounds, _)| child_bounds.contains_rect(area))
+                {
+                    Some((_, child)) => child.remove(area, value, capacity),
+                    None => take(items, area, value),
+                };
+                if removed.is_some() {
+                    *len -= 1;
+                }
+                removed
+            }
+        };
+        if removed.is_some() && self.len() <= capacity {
+            self.merge();
+        }
+        removed
+    }
+
+    /// Collapse a branch back into a single leaf
+    fn merge(&mut self) {
+        if let Node::Branch { .. } = self {
+            let mut items = Vec::with_capacity(self.len());
+            std::mem::replace(self, Node::Leaf(Vec::new())).drain_into(&mut items);
+            *self = Node::Leaf(items);
+        }
+    }
+
+    fn drain_into(self, target: &mut Vec<(TileRect, T)>) {
+        match self {
+            Node::Leaf(items) => target.extend(items),
+            Node::Branch {
+                items, children, ..
+            } => {
+                target.extend(items);
+                let children: [(TileRect, Node<T>); 4] = *children;
+                for (_, child) in children {
+                    child.drain_into(target);
+                }
+            }
+        }
+    }
+
+    fn items(&self) -> &[(TileRect, T)] {
+        match self {
+            Node::Leaf(items) | Node::Branch { items, .. } => items,
+        }
+    }
+
+    fn children(&self) -> &[(TileRect, Node<T>)] {
+        match self {
+            Node::Leaf(_) => &[],
+            Node::Branch { children, .. } => children.as_slice(),
+        }
+    }
+
+    fn query<'a>(&'a self, area: TileRect, found: &mut Vec<(TileRect, &'a T)>) {
+        found.extend(
+            self.items()
+                .iter()
+                .filter(|(item, _)| item.intersects(area))
+                .map(|(item, value)| (*item, value)),
+        );
+        for (bounds, child) in self.children() {
+            if bounds.intersects(area) {
+                child.query(area, found);
+            }
+        }
+    }
+
+    fn nearest<'a>(
+        &'a self,
+        point: Point,
+        metric: Distance,
+        best: &mut Option<(f32, TileRect, &'a T)>,
+    ) {
+        for (area, value) in self.items() {
+            let distance = distance_to_rect(metric, point, *area);
+            if best.map_or(true, |(known, _, _)| distance < known) {
+                *best = Some((distance, *area, value));
+            }
+        }
+
+        let mut children: Vec<(f32, &Node<T>)> = self
+            .children()
+            .iter()
+            .filter(|(bounds, child)| !bounds.is_empty() && child.len() > 0)
+            .map(|(bounds, child)| (distance_to_rect(metric, point, *bounds), child))
+            .collect();
+        children.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (distance, child) in children {
+            if best.map_or(true, |(known, _, _)| distance < known) {
+                child.nearest(point, metric, best);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Region quadtree storing values at points or rectangles inside fixed bounds.
+/// Empty areas cost nothing, crowded areas are split into four quadrants recursively.
+pub struct Quadtree<T> {
+    bounds: TileRect,
+    capacity: usize,
+    root: Node<T>,
+}
+
+impl<T> Quadtree<T> {
+    #[must_use]
+    pub fn new(bounds: TileRect) -> Self {
+        Self::with_node_capacity(bounds, DEFAULT_NODE_CAPACITY)
+    }
+
+    #[must_use]
+    /// Create a tree splitting nodes that hold more than `capacity` items
+    pub fn with_node_capacity(bounds: TileRect, capacity: usize) -> Self {
+        Self {
+            bounds,
+            capacity: capacity.max(1),
+            root: Node::Leaf(Vec::new()),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn bounds(&self) -> TileRect {
+        self.bounds
+    }
+
+    #[inline]
+    #[must_use]
+    /// Number of stored values
+    pub fn len(&self) -> usize {
+        self.root.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert a value at the point, returns false if the point is out of bounds
+    pub fn insert(&mut self, point: Point, value: T) -> bool {
+        self.insert_rect(TileRect::new(point.x, point.y, 1, 1), value)
+    }
+
+    /// Insert a value covering the area, returns false if it's empty or not fully inside bounds
+    pub fn insert_rect(&mut self, area: TileRect, value: T) -> bool {
+        if area.is_empty() || !self.bounds.contains_rect(area) {
+            return false;
+        }
+        self.root.insert(self.bounds, area, value, self.capacity);
+        true
+    }
+
+    /// Remove a value inserted at the point, returns false if it wasn't there
+    pub fn remove(&mut self, point: Point, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.remove_rect(TileRect::new(point.x, point.y, 1, 1), value)
+    }
+
+    /// Remove a value inserted with the area, returns false if it wasn't there.
+    /// Branches left with few items are merged back into leaves.
+    pub fn remove_rect(&mut self, area: TileRect, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.root.remove(area, value, self.capacity).is_some()
+    }
+
+    #[must_use]
+    /// Values whose areas intersect the given one
+    pub fn query(&self, area: TileRect) -> Vec<(TileRect, &T)> {
+        let mut found = Vec::new();
+        self.root.query(area, &mut found);
+        found
+    }
+
+    #[must_use]
+    /// Value nearest to the point, distance to an area is measured to its closest tile
+    pub fn nearest(&self, point: Point, metric: Distance) -> Option<(TileRect, &T)> {
+        let mut best = None;
+        self.root.nearest(point, metric, &mut best);
+        best.map(|(_, area, value)| (area, value))
+    }
+
+    #[must_use]
+    /// Depth of the deepest node, a tree without branches has depth 1
+    pub fn depth(&self) -> usize {
+        fn depth<T>(node: &Node<T>) -> usize {
+            1 + node
+                .children()
+                .iter()
+                .map(|(_, child)| depth(child))
+                .max()
+                .unwrap_or(0)
+        }
+        depth(&self.root)
+    }
+}
+
+fn take<T: PartialEq>(items: &mut Vec<(TileRect, T)>, area: TileRect, value: &T) -> Option<T> {
+    let index = items
+        .iter()
+        .position(|(item, v)| *item == area && v == value)?;
+    Some(items.swap_remove(index).1)
+}
+
+fn distance_to_rect(metric: Distance, point: Point, area: TileRect) -> f32 {
+    metric.between(point, area.clamp(point))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Distance, Point, Quadtree, TileRect};
+
+    #[test]
+    fn insert_and_query() {
+        let mut tree = Quadtree::with_node_capacity(TileRect::with_size(64, 64), 2);
+        for i in 0..20 {
+            assert!(tree.insert(Point::new(i * 3, i * 2), i));
+        }
+        assert!(!tree.insert(Point::new(64, 0), 100));
+        assert!(tree.insert_rect(TileRect::new(8, 9, 20, 20), 200));
+        assert_eq!(21, tree.len());
+        assert!(tree.depth() > 1);
+
+        let mut found: Vec<i32> = tree
+            .query(TileRect::new(0, 0, 10, 10))
+            .into_iter()
+            .map(|(_, &value)| value)
+            .collect();
+        found.sort_unstable();
+        assert_eq!(found, [0, 1, 2, 3, 200]);
+    }
+
+    #[test]
+    fn nearest() {
+        let mut tree = Quadtree::with_node_capacity(TileRect::new(-32, -32, 64, 64), 1);
+        tree.insert(Point::new(-20, -20), "far");
+        tree.insert(Point::new(5, 5), "near");
+        tree.insert_rect(TileRect::new(10, -30, 5, 60), "wall");
+        assert_eq!(
+            Some(&"near"),
+            tree.nearest(Point::new(3, 2), Distance::Euclidean)
+                .map(|(_, value)| value)
+        );
+        assert_eq!(
+            Some(&"wall"),
+            tree.nearest(Point::new(9, 25), Distance::Chebyshev)
+                .map(|(_, value)| value)
+        );
+        assert!(Quadtree::<()>::new(TileRect::with_size(4, 4))
+            .nearest(Point::new(0, 0), Distance::Euclidean)
+            .is_none());
+    }
+
+    #[test]
+    fn remove_merges() {
+        let mut tree = Quadtree::with_node_capacity(TileRect::with_size(16, 16), 2);
+        for i in 0..10 {
+            tree.insert(Point::new(i, i), i);
+        }
+        assert!(tree.depth() > 1);
+        for i in 0..9 {
+            assert!(tree.remove(Point::new(i, i), &i));
+        }
+        assert!(!tree.remove(Point::new(0, 0), &0));
+        assert_eq!(1, tree.len());
+        assert_eq!(1, tree.depth());
+        assert_eq!(1, tree.query(tree.bounds()).len());
+    }
+}
@@ -0,0 +1,229 @@
+use super::{Point, Rect};
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Rectangle of tiles with integer coordinates.
+/// `x` and `y` are the top-left tile, right and bottom edges are exclusive.
+pub struct TileRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl TileRect {
+    #[must_use]
+    /// Create a new rectangle from its top-left corner and size
+    pub const fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        TileRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[must_use]
+    /// Create a rectangle at `(0, 0)` with given size
+    pub const fn with_size(width: i32, height: i32) -> Self {
+        Self::new(0, 0, width, height)
+    }
+
+    #[must_use]
+    /// Create the smallest rectangle containing both corner points (inclusive)
+    pub fn from_corners(a: Point, b: Point) -> Self {
+        let left = a.x.min(b.x);
+        let top = a.y.min(b.y);
+        Self::new(left, top, a.x.max(b.x) - left + 1, a.y.max(b.y) - top + 1)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Top-left tile of the rectangle
+    pub fn position(self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    #[inline]
+    #[must_use]
+    /// First column inside the rectangle
+    pub fn left(self) -> i32 {
+        self.x
+    }
+
+    #[inline]
+    #[must_use]
+    /// First row inside the rectangle
+    pub fn top(self) -> i32 {
+        self.y
+    }
+
+    #[inline]
+    #[must_use]
+    /// First column after the rectangle
+    pub fn right(self) -> i32 {
+        self.x + self.width
+    }
+
+    #[inline]
+    #[must_use]
+    /// First row after the rectangle
+    pub fn bottom(self) -> i32 {
+        self.y + self.height
+    }
+
+    #[inline]
+    #[must_use]
+    /// Check if rectangle contains no tiles
+    pub fn is_empty(self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    /// Number of tiles inside the rectangle
+    pub fn area(self) -> usize {
+        if self.is_empty() {
+            0
+        } else {
+            self.width as usize * self.height as usize
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Central tile of the rectangle, rounded towards top-left
+    pub fn center(self) -> Point {
+        Point::new(self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Check if the point is inside the rectangle
+    pub fn contains(self, point: Point) -> bool {
+        point.x >= self.left()
+            && point.x < self.right()
+            && point.y >= self.top()
+            && point.y < self.bottom()
+    }
+
+    #[must_use]
+    /// Check if other rectangle is fully inside this one
+    pub fn contains_rect(self, other: TileRect) -> bool {
+        other.left() >= self.left()
+            && other.right() <= self.right()
+            && other.top() >= self.top()
+            && other.bottom() <= self.bottom()
+    }
+
+    #[must_use]
+    /// Check if two rectangles share at least one tile
+    pub fn intersects(self, other: TileRect) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.left() < other.right()
+            && other.left() < self.right()
+            && self.top() < other.bottom()
+            && other.top() < self.bottom()
+    }
+
+    #[must_use]
+    /// Common part of two rectangles, if any
+    pub fn intersection(self, other: TileRect) -> Option<TileRect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let left = self.left().max(other.left());
+        let top = self.top().max(other.top());
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        Some(Self::new(left, top, right - left, bottom - top))
+    }
+
+    #[must_use]
+    /// Rectangle moved by the given offset
+    pub fn translated(self, offset: Point) -> Self {
+        Self::new(
+            self.x + offset.x,
+            self.y + offset.y,
+            self.width,
+            self.height,
+        )
+    }
+
+    #[must_use]
+    /// Rectangle grown by `amount` tiles on every side (shrunk if negative)
+    pub fn inflated(self, amount: i32) -> Self {
+        Self::new(
+            self.x - amount,
+            self.y - amount,
+            self.width + amount * 2,
+            self.height + amount * 2,
+        )
+    }
+
+    #[must_use]
+    /// Nearest point inside the rectangle, rectangle must not be empty
+    pub fn clamp(self, point: Point) -> Point {
+        Point::new(
+            point.x.clamp(self.left(), self.right() - 1),
+            point.y.clamp(self.top(), self.bottom() - 1),
+        )
+    }
+
+    /// Iterate over all points inside the rectangle, row by row
+    pub fn points(self) -> impl Iterator<Item = Point> {
+        let (left, right) = (self.left(), self.right());
+        (self.top()..self.bottom()).flat_map(move |y| (left..right).map(move |x| Point::new(x, y)))
+    }
+}
+
+impl From<TileRect> for Rect {
+    #[allow(clippy::cast_precision_loss)]
+    fn from(rect: TileRect) -> Self {
+        Rect::new(
+            rect.x as f32,
+            rect.y as f32,
+            rect.width as f32,
+            rect.height as f32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Point, TileRect};
+
+    #[test]
+    fn contains() {
+        let rect = TileRect::new(1, 2, 3, 4);
+        assert!(rect.contains(Point::new(1, 2)));
+        assert!(rect.contains(Point::new(3, 5)));
+        assert!(!rect.contains(Point::new(4, 5)));
+        assert!(!rect.contains(Point::new(3, 6)));
+        assert!(!rect.contains(Point::new(0, 2)));
+    }
+
+    #[test]
+    fn from_corners() {
+        let rect = TileRect::from_corners(Point::new(5, 1), Point::new(2, 3));
+        assert_eq!(TileRect::new(2, 1, 4, 3), rect);
+        assert_eq!(12, rect.area());
+    }
+
+    #[test]
+    fn intersection() {
+        let a = TileRect::new(0, 0, 4, 4);
+        let b = TileRect::new(2, 3, 5, 5);
+        assert_eq!(Some(TileRect::new(2, 3, 2, 1)), a.intersection(b));
+        assert_eq!(None, a.intersection(TileRect::new(4, 0, 2, 2)));
+    }
+
+    #[test]
+    fn points() {
+        let points: Vec<Point> = TileRect::new(1, 1, 2, 2).points().collect();
+        assert_eq!(points, [(1, 1), (2, 1), (1, 2), (2, 2)]);
+        assert_eq!(0, TileRect::new(0, 0, -1, 3).points().count());
+    }
+}
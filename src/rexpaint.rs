@@ -0,0 +1,340 @@
+//! Reading and writing `REXPaint` `.xp` images: gzip-compressed layers of CP437 glyphs
+//! with foreground and background colours (requires the `rexpaint` feature).
+
+use std::io::{self, Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use super::{cp437, Grid, Point};
+
+/// Format version written by current `REXPaint` releases
+pub const VERSION: i32 = -1;
+
+/// Largest decompressed image [`XpImage::read`] accepts, protecting against gzip bombs
+pub const MAX_XP_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Bytes per stored cell: `i32` glyph, foreground and background RGB
+const CELL_SIZE: usize = 10;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    #[must_use]
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+}
+
+/// Background colour `REXPaint` uses to mark empty cells of a layer
+pub const TRANSPARENT: Rgb = Rgb::new(255, 0, 255);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct XpCell {
+    /// CP437 byte
+    pub glyph: u8,
+    pub fg: Rgb,
+    pub bg: Rgb,
+}
+
+impl XpCell {
+    #[must_use]
+    pub const fn new(glyph: u8, fg: Rgb, bg: Rgb) -> Self {
+        XpCell { glyph, fg, bg }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Unicode character of the glyph
+    pub fn char(self) -> char {
+        cp437::to_char(self.glyph)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Check if the cell is empty, so lower layers show through it
+    pub fn is_transparent(self) -> bool {
+        self.bg == TRANSPARENT
+    }
+}
+
+impl Default for XpCell {
+    fn default() -> Self {
+        XpCell::new(0, Rgb::default(), TRANSPARENT)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawXpImage"))]
+/// `REXPaint` image, all layers have the same size
+pub struct XpImage {
+    pub version: i32,
+    pub layers: Vec<Grid<XpCell>>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+/// Deserialized image before its layer sizes are checked
+struct RawXpImage {
+    version: i32,
+    layers: Vec<Grid<XpCell>>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawXpImage> for XpImage {
+    type Error = &'static str;
+
+    fn try_from(raw: RawXpImage) -> Result<Self, Self::Error> {
+        if !same_size(&raw.layers) {
+            return Err("image layers differ in size");
+        }
+        Ok(XpImage {
+            version: raw.version,
+            layers: raw.layers,
+        })
+    }
+}
+
+impl XpImage {
+    #[must_use]
+    /// Image of `layers` transparent layers of `width` x `height` cells
+    pub fn new(width: i32, height: i32, layers: usize) -> Self {
+        XpImage {
+            version: VERSION,
+            layers: vec![Grid::new(width, height, XpCell::default()); layers],
+        }
+    }
+
+    #[must_use]
+    pub fn width(&self) -> i32 {
+        self.layers.first().map_or(0, Grid::width)
+    }
+
+    #[must_use]
+    pub fn height(&self) -> i32 {
+        self.layers.first().map_or(0, Grid::height)
+    }
+
+    /// Read a gzip-compressed `.xp` file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, the data isn't gzip-compressed,
+    /// decompresses to more than [`MAX_XP_BYTES`] or the image is malformed
+    pub fn read<R: Read>(reader: R) -> io::Result<Self> {
+        Self::read_limited(reader, MAX_XP_BYTES)
+    }
+
+    fn read_limited<R: Read>(reader: R, limit: u64) -> io::Result<Self> {
+        let mut data = Vec::new();
+        GzDecoder::new(reader)
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut data)?;
+        if data.len() as u64 > limit {
+            return Err(invalid("decompressed image is too large"));
+        }
+        Self::from_uncompressed(&data)
+    }
+
+    /// Write the image as a gzip-compressed `.xp` file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        encoder.write_all(&self.to_uncompressed())?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Parse an image from already decompressed data
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is truncated, sizes are negative, layers differ in size
+    /// or a glyph isn't a CP437 byte
+    pub fn from_uncompressed(mut data: &[u8]) -> io::Result<Self> {
+        let version = read_i32(&mut data)?;
+        let layer_count = read_i32(&mut data)?;
+        let mut layers = Vec::with_capacity(usize::try_from(layer_count).unwrap_or(0).min(16));
+        for _ in 0..layer_count.max(0) {
+            let width = read_i32(&mut data)?;
+            let height = read_i32(&mut data)?;
+            if width < 0 || height < 0 {
+                return Err(invalid("negative layer size"));
+            }
+            if layers.first().is_some_and(|first: &Grid<XpCell>| {
+                (first.width(), first.height()) != (width, height)
+            }) {
+                return Err(invalid("layers differ in size"));
+            }
+            // Check the cells are there before allocating what the header claims
+            let cells = usize::try_from(width)
+                .ok()
+                .zip(usize::try_from(height).ok())
+                .and_then(|(w, h)| w.checked_mul(h)?.checked_mul(CELL_SIZE));
+            if cells.map_or(true, |size| size > data.len()) {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+            let mut layer = Grid::new(width, height, XpCell::default());
+            // Cells are stored column by column
+            for x in 0..width {
+                for y in 0..height {
+                    let glyph = u8::try_from(read_i32(&mut data)?)
+                        .map_err(|_| invalid("glyph is not a CP437 byte"))?;
+                    let mut colors = [0; 6];
+                    data.read_exact(&mut colors)?;
+                    let [fg_r, fg_g, fg_b, bg_r, bg_g, bg_b] = colors;
+                    layer[Point::new(x, y)] = XpCell::new(
+                        glyph,
+                        Rgb::new(fg_r, fg_g, fg_b),
+                        Rgb::new(bg_r, bg_g, bg_b),
+                    );
+                }
+            }
+            layers.push(layer);
+        }
+        Ok(XpImage { version, layers })
+    }
+
+    #[must_use]
+    /// Serialize the image without compression
+    pub fn to_uncompressed(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(self.version.to_le_bytes());
+        data.extend(
+            i32::try_from(self.layers.len())
+                .unwrap_or(i32::MAX)
+                .to_le_bytes(),
+        );
+        for layer in &self.layers {
+            data.extend(layer.width().to_le_bytes());
+            data.extend(layer.height().to_le_bytes());
+            for x in 0..layer.width() {
+                for y in 0..layer.height() {
+                    let cell = layer[Point::new(x, y)];
+                    data.extend(i32::from(cell.glyph).to_le_bytes());
+                    data.extend([cell.fg.r, cell.fg.g, cell.fg.b]);
+                    data.extend([cell.bg.r, cell.bg.g, cell.bg.b]);
+                }
+            }
+        }
+        data
+    }
+
+    #[must_use]
+    /// Merge all layers into one, upper layers cover lower ones except where they're transparent
+    pub fn flatten(&self) -> Grid<XpCell> {
+        Grid::from_fn(self.width(), self.height(), |point| {
+            self.layers
+                .iter()
+                .rev()
+                .filter_map(|layer| layer.get(point))
+                .find(|cell| !cell.is_transparent())
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// Check if all layers are as large as the first one
+#[cfg(feature = "serde")]
+fn same_size(layers: &[Grid<XpCell>]) -> bool {
+    layers
+        .windows(2)
+        .all(|pair| (pair[0].width(), pair[0].height()) == (pair[1].width(), pair[1].height()))
+}
+
+fn read_i32(data: &mut &[u8]) -> io::Result<i32> {
+    let mut bytes = [0; 4];
+    data.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, Point, Rgb, XpCell, XpImage, TRANSPARENT};
+
+    #[test]
+    fn roundtrip() {
+        let mut image = XpImage::new(3, 2, 2);
+        let white = Rgb::new(255, 255, 255);
+        image.layers[0][Point::new(2, 0)] = XpCell::new(219, white, Rgb::new(10, 20, 30));
+        image.layers[1][Point::new(0, 1)] = XpCell::new(b'@', white, Rgb::default());
+
+        let mut file = Vec::new();
+        image.write(&mut file).unwrap();
+        assert_eq!([0x1f, 0x8b], file[..2]);
+        let loaded = XpImage::read(file.as_slice()).unwrap();
+        assert_eq!(image, loaded);
+        assert_eq!('█', loaded.layers[0][Point::new(2, 0)].char());
+
+        // Cells are column-major: (0, 1) is the second cell of the second layer
+        let data = image.to_uncompressed();
+        let offset = 8 + (8 + 6 * 10) + 8 + 10;
+        assert_eq!(i32::from(b'@').to_le_bytes(), data[offset..offset + 4]);
+    }
+
+    #[test]
+    fn flatten_and_errors() {
+        let mut image = XpImage::new(2, 1, 2);
+        image.layers[0].fill(XpCell::new(b'.', Rgb::default(), Rgb::default()));
+        image.layers[1][Point::new(1, 0)] = XpCell::new(b'#', Rgb::default(), Rgb::default());
+        let flat = image.flatten();
+        assert_eq!(
+            ".#",
+            flat.cells().iter().map(|c| c.char()).collect::<String>()
+        );
+        assert!(!flat[Point::new(0, 0)].is_transparent());
+        assert_eq!(TRANSPARENT, XpCell::default().bg);
+
+        let data = image.to_uncompressed();
+        assert!(XpImage::from_uncompressed(&data[..data.len() - 1]).is_err());
+        assert!(XpImage::read(data.as_slice()).is_err());
+
+        // Huge size claimed by a short header
+        let mut huge = Vec::new();
+        for value in [-1_i32, 1, 2_000_000_000, 2_000_000_000] {
+            huge.extend(value.to_le_bytes());
+        }
+        huge.extend([0; 8]);
+        assert!(XpImage::from_uncompressed(&huge).is_err());
+
+        // Second layer smaller than the first
+        let mut uneven = XpImage::new(2, 1, 1);
+        uneven.layers.push(Grid::new(1, 1, XpCell::default()));
+        assert!(XpImage::from_uncompressed(&uneven.to_uncompressed()).is_err());
+        #[cfg(feature = "serde")]
+        {
+            let raw = super::RawXpImage {
+                version: uneven.version,
+                layers: uneven.layers,
+            };
+            assert!(XpImage::try_from(raw).is_err());
+        }
+    }
+
+    #[test]
+    fn size_limit() {
+        let image = XpImage::new(20, 20, 3);
+        let mut file = Vec::new();
+        image.write(&mut file).unwrap();
+        let size = image.to_uncompressed().len() as u64;
+        assert!(XpImage::read_limited(file.as_slice(), size).is_ok());
+        assert!(XpImage::read_limited(file.as_slice(), size - 1).is_err());
+    }
+}
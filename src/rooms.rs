@@ -0,0 +1,296 @@
+//! Connecting rooms: build a graph over room centers, reduce it to a minimum
+//! spanning tree, optionally bring back some edges for loops and turn edges into corridors.
+
+use super::pathfinding::astar;
+use super::{Point, TileRect, DIR4};
+
+/// Edge between two rooms given by their indices, smaller index first
+pub type Edge = (usize, usize);
+
+#[must_use]
+/// Edges of the Delaunay triangulation of the points.
+/// Duplicate points are connected to their first occurrence,
+/// collinear points are connected in a chain.
+pub fn delaunay(points: &[Point]) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    let mut unique: Vec<usize> = Vec::new();
+    for (i, point) in points.iter().enumerate() {
+        match unique.iter().find(|&&u| points[u] == *point) {
+            Some(&first) => edges.push(normalize(first, i)),
+            None => unique.push(i),
+        }
+    }
+    if unique.len() < 2 {
+        return finish(edges);
+    }
+
+    let mut vertices: Vec<(f64, f64)> = unique
+        .iter()
+        .map(|&i| (f64::from(points[i].x), f64::from(points[i].y)))
+        .collect();
+    let (min_x, max_x, min_y, max_y) = vertices.iter().fold(
+        (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+        |(min_x, max_x, min_y, max_y), &(x, y)| {
+            (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+        },
+    );
+    let delta = (max_x - min_x).max(max_y - min_y).max(1.0) * 100.0;
+    let (center_x, center_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+    let first_super = vertices.len();
+    vertices.push((center_x - delta, center_y - delta));
+    vertices.push((center_x + delta, center_y - delta));
+    vertices.push((center_x, center_y + delta));
+
+    let mut triangles = vec![counter_clockwise(
+        &vertices,
+        [first_super, first_super + 1, first_super + 2],
+    )];
+    for point in 0..first_super {
+        let (bad, good): (Vec<[usize; 3]>, Vec<[usize; 3]>) = triangles
+            .into_iter()
+            .partition(|&triangle| in_circumcircle(&vertices, triangle, point));
+        triangles = good;
+
+        let bad_edges: Vec<Edge> = bad
+            .iter()
+            .flat_map(|&[a, b, c]| [normalize(a, b), normalize(b, c), normalize(c, a)])
+            .collect();
+        for &(a, b) in &bad_edges {
+            let shared = bad_edges.iter().filter(|&&edge| edge == (a, b)).count() > 1;
+            if !shared {
+                triangles.push(counter_clockwise(&vertices, [a, b, point]));
+            }
+        }
+    }
+
+    for [a, b, c] in triangles {
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            if from < first_super && to < first_super {
+                edges.push(normalize(unique[from], unique[to]));
+            }
+        }
+    }
+    finish(edges)
+}
+
+#[must_use]
+/// Edges from every point to its `k` nearest other points
+pub fn nearest_neighbours(points: &[Point], k: usize) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    for (i, &point) in points.iter().enumerate() {
+        let mut others: Vec<usize> = (0..points.len()).filter(|&j| j != i).collect();
+        others.sort_by_key(|&j| (point.square_distance_to(points[j]), j));
+        edges.extend(others.into_iter().take(k).map(|j| normalize(i, j)));
+    }
+    finish(edges)
+}
+
+#[must_use]
+/// Subset of `edges` connecting all reachable points with the smallest total length (Kruskal)
+pub fn minimum_spanning_tree(points: &[Point], edges: &[Edge]) -> Vec<Edge> {
+    let mut sorted = edges.to_vec();
+    sorted.sort_by_key(|&(a, b)| (points[a].square_distance_to(points[b]), a, b));
+
+    let mut parents: Vec<usize> = (0..points.len()).collect();
+    let mut tree = Vec::new();
+    for (a, b) in sorted {
+        let root_a = find_root(&mut parents, a);
+        let root_b = find_root(&mut parents, b);
+        if root_a != root_b {
+            parents[root_a] = root_b;
+            tree.push(normalize(a, b));
+        }
+    }
+    finish(tree)
+}
+
+#[cfg(feature = "rand")]
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_precision_loss)]
+#[allow(clippy::cast_sign_loss)]
+/// Spanning tree plus randomly chosen `fraction` (`0.0..=1.0`) of the remaining graph edges,
+/// which adds loops to the dungeon layout
+pub fn add_loops<R: rand::Rng + ?Sized>(
+    graph: &[Edge],
+    tree: &[Edge],
+    fraction: f32,
+    rng: &mut R,
+) -> Vec<Edge> {
+    use rand::seq::SliceRandom;
+
+    let tree: Vec<Edge> = finish(tree.iter().map(|&(a, b)| normalize(a, b)).collect());
+    let mut extra: Vec<Edge> = finish(graph.iter().map(|&(a, b)| normalize(a, b)).collect())
+        .into_iter()
+        .filter(|edge| tree.binary_search(edge).is_err())
+        .collect();
+    extra.shuffle(rng);
+    let count = (extra.len() as f32 * fraction.clamp(0.0, 1.0)).round() as usize;
+    extra.truncate(count);
+    extra.extend(tree);
+    finish(extra)
+}
+
+#[must_use]
+/// Straight corridor along Bresenham's line, may contain diagonal steps
+pub fn straight_corridor(from: Point, to: Point) -> Vec<Point> {
+    from.line_to(to)
+}
+
+#[must_use]
+/// Corridor with a single turn, going horizontally or vertically first
+pub fn elbow_corridor(from: Point, to: Point, horizontal_first: bool) -> Vec<Point> {
+    let corner = if horizontal_first {
+        Point::new(to.x, from.y)
+    } else {
+        Point::new(from.x, to.y)
+    };
+    let mut path = from.line_to(corner);
+    path.extend(corner.line_to(to).into_iter().skip(1));
+    path
+}
+
+#[must_use]
+/// Corridor dug with A* without diagonal steps.
+/// `cost` returns the price of digging through a tile or `None` if it can't be dug,
+/// e.g. making room walls expensive keeps corridors from cutting through rooms.
+/// The corridor never leaves `bounds`, usually the whole map.
+pub fn carved_corridor<F>(from: Point, to: Point, bounds: TileRect, cost: F) -> Option<Vec<Point>>
+where
+    F: FnMut(Point) -> Option<f32>,
+{
+    astar(from, to, &DIR4, bounds, cost)
+}
+
+fn normalize(a: usize, b: usize) -> Edge {
+    (a.min(b), a.max(b))
+}
+
+/// Sort and remove duplicate edges and loops
+fn finish(mut edges: Vec<Edge>) -> Vec<Edge> {
+    edges.retain(|&(a, b)| a != b);
+    edges.sort_unstable();
+    edges.dedup();
+    edges
+}
+
+fn find_root(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        parents[node] = parents[parents[node]];
+        node = parents[node];
+    }
+    node
+}
+
+fn orientation(vertices: &[(f64, f64)], [a, b, c]: [usize; 3]) -> f64 {
+    let (ax, ay) = vertices[a];
+    let (bx, by) = vertices[b];
+    let (cx, cy) = vertices[c];
+    (bx - ax) * (cy - ay) - (by - ay) * (cx - ax)
+}
+
+fn counter_clockwise(vertices: &[(f64, f64)], [a, b, c]: [usize; 3]) -> [usize; 3] {
+    if orientation(vertices, [a, b, c]) < 0.0 {
+        [a, c, b]
+    } else {
+        [a, b, c]
+    }
+}
+
+/// Check if the point lies strictly inside the circumcircle of a counter-clockwise triangle
+fn in_circumcircle(vertices: &[(f64, f64)], [a, b, c]: [usize; 3], point: usize) -> bool {
+    let (px, py) = vertices[point];
+    let [(ax, ay), (bx, by), (cx, cy)] =
+        [vertices[a], vertices[b], vertices[c]].map(|(x, y)| (x - px, y - py));
+    let det = (ax * ax + ay * ay) * (bx * cy - cx * by) - (bx * bx + by * by) * (ax * cy - cx * ay)
+        + (cx * cx + cy * cy) * (ax * by - bx * ay);
+    det > 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        carved_corridor, delaunay, elbow_corridor, minimum_spanning_tree, nearest_neighbours,
+        Point, TileRect,
+    };
+
+    #[test]
+    fn square_triangulation() {
+        let points = [
+            Point::new(0, 0),
+            Point::new(10, 0),
+            Point::new(0, 10),
+            Point::new(10, 10),
+            Point::new(5, 4),
+        ];
+        let edges = delaunay(&points);
+        assert_eq!(
+            edges,
+            [
+                (0, 1),
+                (0, 2),
+                (0, 4),
+                (1, 3),
+                (1, 4),
+                (2, 3),
+                (2, 4),
+                (3, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn collinear_and_duplicates() {
+        let points = [
+            Point::new(0, 0),
+            Point::new(4, 0),
+            Point::new(2, 0),
+            Point::new(2, 0),
+        ];
+        assert_eq!(delaunay(&points), [(0, 2), (1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn spanning_tree() {
+        let points = [
+            Point::new(0, 0),
+            Point::new(1, 0),
+            Point::new(10, 0),
+            Point::new(10, 2),
+        ];
+        let graph = nearest_neighbours(&points, 3);
+        assert_eq!(6, graph.len());
+        let tree = minimum_spanning_tree(&points, &graph);
+        assert_eq!(tree, [(0, 1), (1, 2), (2, 3)]);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn loops() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let graph = [(0, 1), (1, 2), (0, 2), (2, 3), (1, 3)];
+        let tree = [(0, 1), (1, 2), (2, 3)];
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(super::add_loops(&graph, &tree, 0.0, &mut rng), tree);
+        assert_eq!(super::add_loops(&graph, &tree, 1.0, &mut rng).len(), 5);
+        assert_eq!(super::add_loops(&graph, &tree, 0.5, &mut rng).len(), 4);
+    }
+
+    #[test]
+    fn corridors() {
+        let path = elbow_corridor(Point::new(0, 0), Point::new(3, 2), true);
+        assert_eq!(path, [(0, 0), (1, 0), (2, 0), (3, 0), (3, 1), (3, 2)]);
+
+        // Room wall at x = 2 is expensive, door at y = 3 is cheap
+        let cost = |p: Point| match (p.x, p.y) {
+            (2, 3) => Some(1.0),
+            (2, _) => Some(50.0),
+            _ => Some(1.0),
+        };
+        let bounds = TileRect::with_size(6, 6);
+        let path = carved_corridor(Point::new(0, 0), Point::new(4, 0), bounds, cost).unwrap();
+        assert!(path.contains(&Point::new(2, 3)));
+    }
+}
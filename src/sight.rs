@@ -0,0 +1,188 @@
+use line_drawing::{Bresenham, Midpoint, Supercover, WalkGrid};
+
+use super::Point;
+
+/// Offsets of near-corner points inside a tile, used by permissive lines
+const CORNERS: [(f32, f32); 4] = [(-0.4, -0.4), (0.4, -0.4), (0.4, 0.4), (-0.4, 0.4)];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Way to pick tiles along a line
+pub enum LineAlgorithm {
+    /// Thinnest line with diagonal steps, not symmetric
+    #[default]
+    Bresenham,
+    /// Line of tiles closest to the real line
+    Midpoint,
+    /// Only orthogonal steps, the line can't slip between two diagonal walls
+    WalkGrid,
+    /// Every tile the real line touches
+    Supercover,
+}
+
+impl LineAlgorithm {
+    #[must_use]
+    /// Tiles between two points, both included
+    pub fn points(self, from: Point, to: Point) -> Vec<Point> {
+        let (start, end) = (from.into(), to.into());
+        match self {
+            LineAlgorithm::Bresenham => Bresenham::new(start, end).map(Point::from).collect(),
+            LineAlgorithm::Midpoint => Vec2Line::new(from, to, (0.0, 0.0), (0.0, 0.0)).collect(),
+            LineAlgorithm::WalkGrid => WalkGrid::new(start, end).map(Point::from).collect(),
+            LineAlgorithm::Supercover => Supercover::new(start, end).map(Point::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Settings for line of sight checks, see [`Point::has_line_of_sight_with`]
+pub struct LineOfSight {
+    pub algorithm: LineAlgorithm,
+    /// Also try the line drawn backwards and lines between corners of the two tiles,
+    /// so peeking around a corner or through a diagonal gap works in both directions
+    pub permissive: bool,
+}
+
+impl LineOfSight {
+    #[must_use]
+    pub const fn new(algorithm: LineAlgorithm) -> Self {
+        LineOfSight {
+            algorithm,
+            permissive: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_permissive(mut self, permissive: bool) -> Self {
+        self.permissive = permissive;
+        self
+    }
+
+    /// Check tiles between the points, the points themselves aren't checked
+    ///
+    /// # Errors
+    ///
+    /// Returns the first blocking tile on the way from `from`.
+    /// In permissive mode it's the one on the main line, when all candidate lines are blocked.
+    pub fn check<F>(self, from: Point, to: Point, mut is_transparent: F) -> Result<(), Point>
+    where
+        F: FnMut(Point) -> bool,
+    {
+        let mut first_blocker = |line: &mut dyn Iterator<Item = Point>| {
+            line.filter(|&point| point != from && point != to)
+                .find(|&point| !is_transparent(point))
+        };
+
+        let Some(blocker) = first_blocker(&mut self.algorithm.points(from, to).into_iter()) else {
+            return Ok(());
+        };
+        if !self.permissive {
+            return Err(blocker);
+        }
+
+        let mut backwards = self.algorithm.points(to, from);
+        backwards.reverse();
+        if first_blocker(&mut backwards.into_iter()).is_none() {
+            return Ok(());
+        }
+        for corner in CORNERS {
+            let lines = [
+                Vec2Line::new(from, to, (0.0, 0.0), corner),
+                Vec2Line::new(from, to, corner, (0.0, 0.0)),
+            ];
+            for mut line in lines {
+                if first_blocker(&mut line).is_none() {
+                    return Ok(());
+                }
+            }
+        }
+        Err(blocker)
+    }
+}
+
+/// Midpoint line between points inside two tiles
+struct Vec2Line(Midpoint<f32, i32>);
+
+impl Vec2Line {
+    #[allow(clippy::cast_precision_loss)]
+    fn new(from: Point, to: Point, from_offset: (f32, f32), to_offset: (f32, f32)) -> Self {
+        Vec2Line(Midpoint::new(
+            (from.x as f32 + from_offset.0, from.y as f32 + from_offset.1),
+            (to.x as f32 + to_offset.0, to.y as f32 + to_offset.1),
+        ))
+    }
+}
+
+impl Iterator for Vec2Line {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Point::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineAlgorithm, LineOfSight, Point};
+
+    #[test]
+    fn blocked_and_clear() {
+        let wall = |p: Point| p != Point::new(3, 0);
+        let from = Point::new(0, 0);
+        assert_eq!(Ok(()), from.has_line_of_sight(Point::new(3, 0), wall));
+        assert_eq!(
+            Err(Point::new(3, 0)),
+            from.has_line_of_sight(Point::new(6, 0), wall)
+        );
+        assert_eq!(Ok(()), from.has_line_of_sight(Point::new(6, 3), wall));
+    }
+
+    #[test]
+    fn algorithms() {
+        let (from, to) = (Point::new(0, 0), Point::new(2, 1));
+        assert_eq!(3, LineAlgorithm::Bresenham.points(from, to).len());
+        assert_eq!(4, LineAlgorithm::WalkGrid.points(from, to).len());
+        for algorithm in [
+            LineAlgorithm::Bresenham,
+            LineAlgorithm::Midpoint,
+            LineAlgorithm::WalkGrid,
+            LineAlgorithm::Supercover,
+        ] {
+            let points = algorithm.points(from, to);
+            assert_eq!(Some(&from), points.first());
+            assert_eq!(Some(&to), points.last());
+        }
+
+        // Diagonal gap between two walls
+        let walls = [Point::new(1, 0), Point::new(0, 1)];
+        let open = |p: Point| !walls.contains(&p);
+        let target = Point::new(1, 1);
+        assert_eq!(Ok(()), Point::new(0, 0).has_line_of_sight(target, open));
+        assert!(Point::new(0, 0)
+            .has_line_of_sight_with(target, LineOfSight::new(LineAlgorithm::WalkGrid), open)
+            .is_err());
+    }
+
+    #[test]
+    fn permissive() {
+        // Pillar on the Bresenham line, other candidate lines pass beside it
+        let open = |p: Point| p != Point::new(2, 0);
+        let (from, to) = (Point::new(0, 0), Point::new(4, 1));
+        let strict = LineOfSight::new(LineAlgorithm::Bresenham);
+        assert_eq!(
+            Err(Point::new(2, 0)),
+            from.has_line_of_sight_with(to, strict, open)
+        );
+        assert_eq!(
+            Ok(()),
+            from.has_line_of_sight_with(to, strict.with_permissive(true), open)
+        );
+
+        let blocked = |p: Point| p.x != 2;
+        assert_eq!(
+            Err(Point::new(2, 0)),
+            from.has_line_of_sight_with(to, strict.with_permissive(true), blocked)
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+
+use super::{Distance, Point, TileRect};
+
+#[derive(Clone, Debug)]
+/// Values stored at points and grouped into square buckets,
+/// so lookups only scan buckets near the queried area.
+pub struct SpatialHash<T> {
+    bucket_size: i32,
+    buckets: HashMap<Point, Vec<(Point, T)>>,
+    len: usize,
+}
+
+impl<T> SpatialHash<T> {
+    #[must_use]
+    /// Create an empty hash with `bucket_size` x `bucket_size` tiles buckets.
+    /// Buckets about as big as a typical query radius work best.
+    pub fn new(bucket_size: i32) -> Self {
+        Self {
+            bucket_size: bucket_size.max(1),
+            buckets: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn bucket_size(&self) -> i32 {
+        self.bucket_size
+    }
+
+    #[inline]
+    #[must_use]
+    /// Number of stored values
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+        self.len = 0;
+    }
+
+    pub fn insert(&mut self, point: Point, value: T) {
+        let bucket = self.bucket_of(point);
+        self.buckets.entry(bucket).or_default().push((point, value));
+        self.len += 1;
+    }
+
+    /// Remove a value stored at the point, returns false if it wasn't there
+    pub fn remove(&mut self, point: Point, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.take(point, value).is_some()
+    }
+
+    /// Move a value from one point to another, returns false if it wasn't at `from`
+    pub fn relocate(&mut self, from: Point, to: Point, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        if let Some(value) = self.take(from, value) {
+            self.insert(to, value);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Iterate over all values in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.buckets
+            .values()
+            .flatten()
+            .map(|(point, value)| (*point, value))
+    }
+
+    /// Iterate over values stored exactly at the point
+    pub fn at(&self, point: Point) -> impl Iterator<Item = &T> {
+        self.buckets
+            .get(&self.bucket_of(point))
+            .into_iter()
+            .flatten()
+            .filter(move |(p, _)| *p == point)
+            .map(|(_, value)| value)
+    }
+
+    /// Iterate over values inside the rectangle
+    pub fn in_rect(&self, rect: TileRect) -> impl Iterator<Item = (Point, &T)> {
+        let (low, high) = if rect.is_empty() {
+            // Empty span
+            (Point::new(1, 1), Point::new(0, 0))
+        } else {
+            (
+                self.bucket_of(rect.position()),
+                self.bucket_of(Point::new(rect.right() - 1, rect.bottom() - 1)),
+            )
+        };
+        self.scan(low, high)
+            .filter(move |(point, _)| rect.contains(*point))
+    }
+
+    /// Iterate over values not farther than `radius` from the center under the metric
+    pub fn in_radius(
+        &self,
+        center: Point,
+        radius: u32,
+        metric: Distance,
+    ) -> impl Iterator<Item = (Point, &T)> {
+        let reach = i32::try_from(radius).unwrap_or(i32::MAX);
+        let low = Point::new(
+            center.x.saturating_sub(reach),
+            center.y.saturating_sub(reach),
+        );
+        let high = Point::new(
+            center.x.saturating_add(reach),
+            center.y.saturating_add(reach),
+        );
+        self.scan(self.bucket_of(low), self.bucket_of(high))
+            .filter(move |(point, _)| within(metric, center, *point, radius))
+    }
+
+    #[must_use]
+    /// Up to `k` values nearest to the point under the metric, closest first
+    pub fn nearest(&self, point: Point, k: usize, metric: Distance) -> Vec<(Point, &T)> {
+        let mut found: Vec<(f32, Point, &T)> = Vec::new();
+        let mut seen = 0;
+        let home = self.bucket_of(point);
+        let mut ring: i32 = 0;
+        while seen < self.len && k > 0 {
+            // Every tile in this ring is at least this far in Chebyshev metric,
+            // which never exceeds any of the other metrics
+            #[allow(clippy::cast_precision_loss)]
+            let closest_possible = (ring - 1).max(0) as f32 * self.bucket_size as f32;
+            if found.len() >= k && closest_possible > found[k - 1].0 {
+                break;
+            }
+            let ring_size = if ring == 0 {
+                1
+            } else {
+                ring.unsigned_abs() as usize * 8
+            };
+            if ring_size > self.buckets.len() {
+                // Fewer occupied buckets than buckets in the ring, check the rest directly
+                let rest = self
+                    .buckets
+                    .iter()
+                    .filter(|(bucket, _)| bucket.chebyshev_distance_to(home) >= ring.unsigned_abs())
+                    .flat_map(|(_, items)| items);
+                found.extend(rest.map(|(p, value)| (metric.between(point, *p), *p, value)));
+                found.sort_by(|a, b| a.0.total_cmp(&b.0));
+                found.truncate(k);
+                break;
+            }
+            for bucket in ring_points(home, ring) {
+                let Some(items) = self.buckets.get(&bucket) else {
+                    continue;
+                };
+                seen += items.len();
+                found.extend(
+                    items
+                        .iter()
+                        .map(|(p, value)| (metric.between(point, *p), *p, value)),
+                );
+            }
+            found.sort_by(|a, b| a.0.total_cmp(&b.0));
+            found.truncate(k);
+            ring += 1;
+        }
+        found.into_iter().map(|(_, p, value)| (p, value)).collect()
+    }
+
+    fn bucket_of(&self, point: Point) -> Point {
+        Point::new(
+            point.x.div_euclid(self.bucket_size),
+            point.y.div_euclid(self.bucket_size),
+        )
+    }
+
+    /// Values in buckets from `low` to `high` inclusive.
+    /// Looks up every bucket of the span, or checks every occupied bucket if there are fewer.
+    fn scan(&self, low: Point, high: Point) -> impl Iterator<Item = (Point, &T)> {
+        let width = (i64::from(high.x) - i64::from(low.x) + 1).max(0);
+        let height = (i64::from(high.y) - i64::from(low.y) + 1).max(0);
+        let occupied = i64::try_from(self.buckets.len()).unwrap_or(i64::MAX);
+        let dense = width.saturating_mul(height) <= occupied;
+        let in_span = move |bucket: &Point| {
+            (low.x..=high.x).contains(&bucket.x) && (low.y..=high.y).contains(&bucket.y)
+        };
+        let by_span = dense
+            .then(|| {
+                (low.y..=high.y)
+                    .flat_map(move |y| (low.x..=high.x).map(move |x| Point::new(x, y)))
+                    .filter_map(|bucket| self.buckets.get(&bucket))
+            })
+            .into_iter()
+            .flatten();
+        let by_occupied = (!dense)
+            .then(|| {
+                self.buckets
+                    .iter()
+                    .filter(move |(bucket, _)| in_span(bucket))
+                    .map(|(_, items)| items)
+            })
+            .into_iter()
+            .flatten();
+        by_span
+            .chain(by_occupied)
+            .flatten()
+            .map(|(point, value)| (*point, value))
+    }
+
+    fn take(&mut self, point: Point, value: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let bucket = self.bucket_of(point);
+        let items = self.buckets.get_mut(&bucket)?;
+        let index = items.iter().position(|(p, v)| *p == point && v == value)?;
+        let (_, value) = items.swap_remove(index);
+        if items.is_empty() {
+            self.buckets.remove(&bucket);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+}
+
+impl<T> Default for SpatialHash<T> {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+/// Buckets exactly `ring` buckets away from `home` in Chebyshev metric
+fn ring_points(home: Point, ring: i32) -> impl Iterator<Item = Point> {
+    (-ring..=ring)
+        .flat_map(move |i| {
+            let side = i.abs() < ring;
+            [
+                Some((i, -ring)),
+                (ring > 0).then_some((i, ring)),
+                side.then_some((-ring, i)),
+                side.then_some((ring, i)),
+            ]
+        })
+        .flatten()
+        .filter_map(move |(dx, dy)| {
+            Some(Point::new(home.x.checked_add(dx)?, home.y.checked_add(dy)?))
+        })
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn within(metric: Distance, a: Point, b: Point, radius: u32) -> bool {
+    match metric {
+        Distance::Euclidean => {
+            u64::from(a.square_distance_to(b)) <= u64::from(radius) * u64::from(radius)
+        }
+        Distance::Manhattan => a.manhattan_distance_to(b) <= radius,
+        Distance::Chebyshev => a.chebyshev_distance_to(b) <= radius,
+        Distance::Octile => metric.between(a, b) <= radius as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Distance, Point, SpatialHash, TileRect};
+
+    fn sample() -> SpatialHash<&'static str> {
+        let mut hash = SpatialHash::new(4);
+        hash.insert(Point::new(0, 0), "rat");
+        hash.insert(Point::new(3, 4), "orc");
+        hash.insert(Point::new(-4, -1), "bat");
+        hash.insert(Point::new(20, 20), "dragon");
+        hash
+    }
+
+    #[test]
+    fn radius_queries() {
+        let hash = sample();
+        let mut names: Vec<&str> = hash
+            .in_radius(Point::new(0, 0), 5, Distance::Euclidean)
+            .map(|(_, &name)| name)
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["bat", "orc", "rat"]);
+        assert_eq!(
+            2,
+            hash.in_radius(Point::new(0, 0), 5, Distance::Manhattan)
+                .count()
+        );
+        assert_eq!(
+            3,
+            hash.in_radius(Point::new(0, 0), 5, Distance::Chebyshev)
+                .count()
+        );
+    }
+
+    #[test]
+    fn rect_query() {
+        let hash = sample();
+        let found: Vec<&str> = hash
+            .in_rect(TileRect::new(-5, -1, 6, 2))
+            .map(|(_, &name)| name)
+            .collect();
+        assert_eq!(2, found.len());
+        assert!(found.contains(&"bat") && found.contains(&"rat"));
+    }
+
+    #[test]
+    fn move_and_remove() {
+        let mut hash = sample();
+        assert!(hash.relocate(Point::new(20, 20), Point::new(1, 1), &"dragon"));
+        assert!(!hash.relocate(Point::new(20, 20), Point::new(1, 1), &"dragon"));
+        assert_eq!(
+            vec![&"dragon"],
+            hash.at(Point::new(1, 1)).collect::<Vec<_>>()
+        );
+        assert!(hash.remove(Point::new(0, 0), &"rat"));
+        assert!(!hash.remove(Point::new(0, 0), &"rat"));
+        assert_eq!(3, hash.len());
+    }
+
+    #[test]
+    fn nearest() {
+        let hash = sample();
+        let nearest = hash.nearest(Point::new(19, 19), 2, Distance::Euclidean);
+        assert_eq!(
+            nearest,
+            [(Point::new(20, 20), &"dragon"), (Point::new(3, 4), &"orc")]
+        );
+        assert_eq!(
+            4,
+            hash.nearest(Point::new(0, 0), 10, Distance::Chebyshev)
+                .len()
+        );
+        assert!(hash
+            .nearest(Point::new(0, 0), 0, Distance::Chebyshev)
+            .is_empty());
+    }
+
+    #[test]
+    fn far_away_queries() {
+        let mut hash = SpatialHash::new(1);
+        hash.insert(Point::new(0, 0), "rat");
+        hash.insert(Point::new(i32::MAX, i32::MAX), "star");
+        // Huge spans check the few occupied buckets instead of every bucket in range
+        assert_eq!(
+            2,
+            hash.in_radius(Point::new(0, 0), u32::MAX, Distance::Chebyshev)
+                .count()
+        );
+        assert_eq!(
+            vec![(Point::new(i32::MAX, i32::MAX), &"star")],
+            hash.in_radius(Point::new(i32::MAX, i32::MAX), 5, Distance::Chebyshev)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            hash.nearest(Point::new(-1_000_000, 5), 2, Distance::Chebyshev),
+            [
+                (Point::new(0, 0), &"rat"),
+                (Point::new(i32::MAX, i32::MAX), &"star")
+            ]
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Word-wrapped text laid out into a rectangle of CP437 cells, e.g. for message logs and dialogs.
+
+use super::{cp437, Point, TileRect};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alignment {
+    pub horizontal: Align,
+    pub vertical: VerticalAlign,
+}
+
+impl Alignment {
+    #[must_use]
+    pub const fn new(horizontal: Align, vertical: VerticalAlign) -> Self {
+        Alignment {
+            horizontal,
+            vertical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+/// Text placed into a rectangle
+pub struct TextLayout {
+    /// CP437 bytes of visible characters, spaces between words included
+    pub cells: Vec<(Point, u8)>,
+    /// Wrapped lines that didn't fit below the rectangle
+    pub overflow: Vec<String>,
+}
+
+impl TextLayout {
+    #[inline]
+    #[must_use]
+    pub fn is_overflowing(&self) -> bool {
+        !self.overflow.is_empty()
+    }
+}
+
+#[must_use]
+/// Lay out the text in the rectangle, wrapping lines with [`wrap`]
+pub fn layout(text: &str, rect: TileRect, alignment: Alignment) -> TextLayout {
+    let mut lines = wrap(text, rect.width);
+    let visible = lines.len().min(usize::try_from(rect.height).unwrap_or(0));
+    let overflow = lines.split_off(visible);
+    let free_rows = rect.height - count(&lines);
+    let top = rect.y
+        + match alignment.vertical {
+            VerticalAlign::Top => 0,
+            VerticalAlign::Middle => free_rows / 2,
+            VerticalAlign::Bottom => free_rows,
+        };
+
+    let mut cells = Vec::new();
+    for (y, line) in (top..).zip(&lines) {
+        let free_columns = rect.width - count(line.chars());
+        let left = rect.x
+            + match alignment.horizontal {
+                Align::Left => 0,
+                Align::Center => free_columns / 2,
+                Align::Right => free_columns,
+            };
+        cells.extend(
+            (left..)
+                .zip(line.chars())
+                .map(|(x, c)| (Point::new(x, y), cp437::to_byte(c))),
+        );
+    }
+    TextLayout { cells, overflow }
+}
+
+#[must_use]
+/// Split the text into lines of at most `width` characters on spaces and line breaks.
+/// Words longer than a line are broken with a hyphen.
+pub fn wrap(text: &str, width: i32) -> Vec<String> {
+    let width = usize::try_from(width).unwrap_or(0);
+    if width == 0 {
+        return Vec::new();
+    }
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line: Vec<char> = Vec::new();
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            let separator = usize::from(!line.is_empty());
+            if line.len() + separator + word.len() <= width {
+                if separator > 0 {
+                    line.push(' ');
+                }
+                line.extend(word);
+                continue;
+            }
+            if word.len() <= width {
+                lines.push(line.drain(..).collect());
+                line.extend(word);
+                continue;
+            }
+            // Too long for any line: hyphenate, starting on the current line if two letters fit
+            let room = width.saturating_sub(line.len() + separator);
+            if !line.is_empty() {
+                if room < 3 {
+                    lines.push(line.drain(..).collect());
+                } else {
+                    line.push(' ');
+                }
+            }
+            while line.len() + word.len() > width {
+                let take = if width - line.len() > 1 {
+                    width - line.len() - 1
+                } else {
+                    width - line.len()
+                };
+                line.extend(word.drain(..take));
+                if line.len() < width {
+                    line.push('-');
+                }
+                lines.push(line.drain(..).collect());
+            }
+            line.extend(word);
+        }
+        lines.push(line.into_iter().collect());
+    }
+    lines
+}
+
+/// Number of items as a coordinate
+fn count<I: IntoIterator>(items: I) -> i32 {
+    i32::try_from(items.into_iter().count()).unwrap_or(i32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{layout, wrap, Align, Alignment, Point, TileRect, VerticalAlign};
+
+    #[test]
+    fn wrapping() {
+        assert_eq!(
+            wrap("The quick brown fox jumps", 10),
+            ["The quick", "brown fox", "jumps"]
+        );
+        assert_eq!(wrap("a\n\nb  c", 10), ["a", "", "b c"]);
+        assert_eq!(wrap("go unbelievably", 6), ["go un-", "belie-", "vably"]);
+        assert_eq!(wrap("abcdef", 1), ["a", "b", "c", "d", "e", "f"]);
+        assert!(wrap("anything", 0).is_empty());
+    }
+
+    #[test]
+    fn aligned_layout() {
+        let rect = TileRect::new(10, 5, 7, 3);
+        let centered = layout(
+            "Hi ♥",
+            rect,
+            Alignment::new(Align::Center, VerticalAlign::Middle),
+        );
+        assert_eq!(
+            centered.cells,
+            [
+                (Point::new(11, 6), b'H'),
+                (Point::new(12, 6), b'i'),
+                (Point::new(13, 6), b' '),
+                (Point::new(14, 6), 3),
+            ]
+        );
+        assert!(!centered.is_overflowing());
+
+        let right = layout(
+            "a b",
+            rect,
+            Alignment::new(Align::Right, VerticalAlign::Bottom),
+        );
+        assert_eq!((Point::new(14, 7), b'a'), right.cells[0]);
+
+        let long = layout("one two three four five", rect, Alignment::default());
+        assert_eq!(long.overflow, ["five"]);
+        assert_eq!((Point::new(10, 5), b'o'), long.cells[0]);
+        assert!(long.cells.iter().all(|(p, _)| rect.contains(*p)));
+    }
+}
@@ -0,0 +1,273 @@
+use super::fov::field_of_view;
+use super::pathfinding::astar_with;
+use super::{Direction, Distance, Point, TileRect};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawTorus"))]
+/// World of given size whose opposite edges are glued together,
+/// so walking off the east edge brings you back at the west edge.
+pub struct Torus {
+    width: i32,
+    height: i32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+/// Deserialized torus before its size is checked
+struct RawTorus {
+    width: i32,
+    height: i32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawTorus> for Torus {
+    type Error = &'static str;
+
+    fn try_from(raw: RawTorus) -> Result<Self, Self::Error> {
+        Torus::new(raw.width, raw.height).ok_or("torus size must be positive")
+    }
+}
+
+impl Torus {
+    #[must_use]
+    /// World of `width` x `height` tiles, `None` unless both are positive
+    pub const fn new(width: i32, height: i32) -> Option<Self> {
+        if width > 0 && height > 0 {
+            Some(Torus { width, height })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn width(self) -> i32 {
+        self.width
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn height(self) -> i32 {
+        self.height
+    }
+
+    #[inline]
+    #[must_use]
+    /// Tiles of the world
+    pub fn rect(self) -> TileRect {
+        TileRect::with_size(self.width, self.height)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Same point moved inside `0..width` x `0..height`
+    pub fn wrap(self, point: Point) -> Point {
+        Point::new(
+            point.x.rem_euclid(self.width),
+            point.y.rem_euclid(self.height),
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    /// Step in the direction, wrapping around the edges
+    pub fn add(self, point: Point, direction: Direction) -> Point {
+        self.wrap(point + direction)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Shortest offset leading from one point to another, crossing edges if it's shorter.
+    /// Every component is in `-size / 2..=size / 2`.
+    pub fn delta(self, from: Point, to: Point) -> Point {
+        Point::new(
+            shortest(to.x - from.x, self.width),
+            shortest(to.y - from.y, self.height),
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    /// Square distance along the shortest offset
+    pub fn square_distance(self, from: Point, to: Point) -> u32 {
+        self.delta(from, to).square_distance_to(Point::default())
+    }
+
+    #[inline]
+    #[must_use]
+    /// Distance along the shortest offset under the metric
+    pub fn distance(self, from: Point, to: Point, metric: Distance) -> f32 {
+        metric.between(Point::default(), self.delta(from, to))
+    }
+
+    #[inline]
+    #[must_use]
+    /// Direction of the shortest offset to other point
+    pub fn direction_to(self, from: Point, to: Point) -> Direction {
+        Direction::from(self.delta(from, to))
+    }
+
+    #[must_use]
+    /// Points of the shortest line between two points, all of them wrapped into the world
+    pub fn line(self, from: Point, to: Point) -> Vec<Point> {
+        let from = self.wrap(from);
+        from.line_to(from + self.delta(from, to))
+            .into_iter()
+            .map(|point| self.wrap(point))
+            .collect()
+    }
+
+    #[must_use]
+    /// A* that may cross the edges of the world, see [`crate::pathfinding::astar`].
+    /// `cost` is only called with wrapped points, the path is wrapped too.
+    pub fn astar<F>(
+        self,
+        start: Point,
+        goal: Point,
+        directions: &[Direction],
+        mut cost: F,
+    ) -> Option<Vec<Point>>
+    where
+        F: FnMut(Point) -> Option<f32>,
+    {
+        let goal = self.wrap(goal);
+        let metric = if directions.iter().any(|dir| dir.is_diagonal()) {
+            Distance::Chebyshev
+        } else {
+            Distance::Manhattan
+        };
+        astar_with(
+            self.wrap(start),
+            goal,
+            |point| {
+                directions
+                    .iter()
+                    .map(|&dir| self.add(point, dir))
+                    .filter_map(|next| cost(next).map(|step| (next, step)))
+                    .collect::<Vec<_>>()
+            },
+            |point| self.distance(point, goal, metric),
+            self.rect().area(),
+        )
+    }
+
+    #[must_use]
+    /// Field of view that may look across the edges of the world, see [`field_of_view`].
+    /// `is_transparent` is only called with wrapped points, visible points are wrapped too
+    /// and every tile is listed once, even if the view wraps around to it again.
+    /// The radius is capped at `width + height`, every tile has a copy closer than that.
+    pub fn field_of_view<F>(self, origin: Point, radius: u32, mut is_transparent: F) -> Vec<Point>
+    where
+        F: FnMut(Point) -> bool,
+    {
+        let radius = radius.min((self.width + self.height).unsigned_abs());
+        let mut visible: Vec<Point> = field_of_view(self.wrap(origin), radius, |point| {
+            is_transparent(self.wrap(point))
+        })
+        .into_iter()
+        .map(|point| self.wrap(point))
+        .collect();
+        visible.sort_unstable_by_key(|point| (point.y, point.x));
+        visible.dedup();
+        visible
+    }
+}
+
+/// Offset in `-size / 2..=size / 2` equivalent to `delta` modulo `size`
+fn shortest(delta: i32, size: i32) -> i32 {
+    let delta = delta.rem_euclid(size);
+    if delta > size / 2 {
+        delta - size
+    } else {
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Direction, Distance, Point, Torus};
+    use crate::DIR8;
+
+    #[test]
+    fn positive_size() {
+        assert_eq!(None, Torus::new(0, 8));
+        assert_eq!(None, Torus::new(10, -1));
+        let torus = Torus::new(10, 8).unwrap();
+        assert_eq!((10, 8), (torus.width(), torus.height()));
+    }
+
+    #[test]
+    fn wrapping_steps() {
+        let torus = Torus::new(10, 8).unwrap();
+        assert_eq!(
+            Point::new(0, 7),
+            torus.add(Point::new(9, 0), Direction::NorthEast)
+        );
+        assert_eq!(Point::new(9, 1), torus.wrap(Point::new(-1, -7)));
+    }
+
+    #[test]
+    fn shortest_delta() {
+        let torus = Torus::new(10, 8).unwrap();
+        assert_eq!(
+            Point::new(2, -1),
+            torus.delta(Point::new(9, 0), Point::new(1, 7))
+        );
+        assert_eq!(
+            Point::new(-3, 3),
+            torus.delta(Point::new(2, 2), Point::new(9, 5))
+        );
+        assert_eq!(
+            Direction::SouthEast,
+            torus.direction_to(Point::new(9, 0), Point::new(1, 1))
+        );
+        assert_eq!(5, torus.square_distance(Point::new(9, 0), Point::new(1, 7)));
+        assert!(
+            (torus.distance(Point::new(0, 0), Point::new(9, 7), Distance::Chebyshev) - 1.0).abs()
+                < f32::EPSILON
+        );
+        assert!(
+            (torus.distance(Point::new(0, 0), Point::new(9, 7), Distance::Manhattan) - 2.0).abs()
+                < f32::EPSILON
+        );
+    }
+
+    #[test]
+    fn wrapped_line() {
+        let torus = Torus::new(10, 10).unwrap();
+        assert_eq!(
+            torus.line(Point::new(8, 5), Point::new(1, 5)),
+            [(8, 5), (9, 5), (0, 5), (1, 5)]
+        );
+    }
+
+    #[test]
+    fn path_across_edge() {
+        let torus = Torus::new(10, 10).unwrap();
+        // Wall at x = 5 blocks the direct way
+        let cost = |p: Point| (p.x != 5).then_some(1.0);
+        let path = torus
+            .astar(Point::new(3, 0), Point::new(7, 0), &DIR8, cost)
+            .unwrap();
+        assert_eq!(7, path.len());
+        assert!(path.iter().any(|p| p.x == 0));
+        assert!(path.iter().all(|p| p.x != 5));
+    }
+
+    #[test]
+    fn view_across_edge() {
+        let torus = Torus::new(10, 10).unwrap();
+        let visible = torus.field_of_view(Point::new(0, 0), 2, |_| true);
+        assert!(visible.contains(&Point::new(8, 0)));
+        assert!(visible.contains(&Point::new(9, 9)));
+        assert_eq!(13, visible.len());
+
+        // Wide open small world: the view wraps onto itself, every tile is listed once
+        let small = Torus::new(3, 2).unwrap();
+        assert_eq!(
+            6,
+            small.field_of_view(Point::new(4, 7), 100, |_| true).len()
+        );
+    }
+}
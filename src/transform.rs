@@ -0,0 +1,239 @@
+use super::{ConvertError, Direction, Point, TwoDimDirection};
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// One of the eight symmetries of a square: rotations by multiples of 90 degrees
+/// and reflections. Rotations are clockwise on screen, where y axis points down.
+pub enum Transform {
+    #[default]
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirror left to right, x becomes -x
+    FlipHorizontal,
+    /// Mirror top to bottom, y becomes -y
+    FlipVertical,
+    /// Mirror over the main diagonal, x and y are swapped
+    Transpose,
+    /// Mirror over the other diagonal
+    AntiTranspose,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::Transpose,
+        Transform::AntiTranspose,
+    ];
+
+    #[must_use]
+    /// Rotation by `quarter_turns` * 90 degrees clockwise, negative values turn counterclockwise
+    pub fn rotation(quarter_turns: i32) -> Self {
+        match quarter_turns.rem_euclid(4) {
+            0 => Transform::Identity,
+            1 => Transform::Rotate90,
+            2 => Transform::Rotate180,
+            _ => Transform::Rotate270,
+        }
+    }
+
+    /// Matrix `[xx, xy, yx, yy]` so that `x' = xx * x + xy * y` and `y' = yx * x + yy * y`
+    fn matrix(self) -> [i32; 4] {
+        match self {
+            Transform::Identity => [1, 0, 0, 1],
+            Transform::Rotate90 => [0, -1, 1, 0],
+            Transform::Rotate180 => [-1, 0, 0, -1],
+            Transform::Rotate270 => [0, 1, -1, 0],
+            Transform::FlipHorizontal => [-1, 0, 0, 1],
+            Transform::FlipVertical => [1, 0, 0, -1],
+            Transform::Transpose => [0, 1, 1, 0],
+            Transform::AntiTranspose => [0, -1, -1, 0],
+        }
+    }
+
+    fn from_matrix(matrix: [i32; 4]) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|transform| transform.matrix() == matrix)
+            .expect("matrix is not a symmetry of the square")
+    }
+
+    #[inline]
+    #[must_use]
+    /// Check if the transform mirrors, so it can't be done by rotating
+    pub fn is_reflection(self) -> bool {
+        matches!(
+            self,
+            Transform::FlipHorizontal
+                | Transform::FlipVertical
+                | Transform::Transpose
+                | Transform::AntiTranspose
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    /// Check if width and height are swapped by the transform
+    pub fn swaps_axes(self) -> bool {
+        self.matrix()[0] == 0
+    }
+
+    #[must_use]
+    /// Transform doing `self` first and `next` after it
+    pub fn then(self, next: Transform) -> Self {
+        // Columns of the matrix are the images of the unit offsets
+        let x_axis = next.apply(self.apply(Point::new(1, 0)));
+        let y_axis = next.apply(self.apply(Point::new(0, 1)));
+        Self::from_matrix([x_axis.x, y_axis.x, x_axis.y, y_axis.y])
+    }
+
+    #[must_use]
+    /// Transform undoing this one
+    pub fn inverse(self) -> Self {
+        match self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            other => other,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    /// Transform an offset around `(0, 0)`
+    pub fn apply(self, offset: Point) -> Point {
+        let [xx, xy, yx, yy] = self.matrix();
+        Point::new(xx * offset.x + xy * offset.y, yx * offset.x + yy * offset.y)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Transform a point around the origin
+    pub fn apply_around(self, point: Point, origin: Point) -> Point {
+        origin + self.apply(point - origin)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Size of a `width` x `height` box after the transform
+    pub fn size(self, width: i32, height: i32) -> (i32, i32) {
+        if self.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    #[must_use]
+    /// Transform a point of a `width` x `height` box with `(0, 0)` at top-left,
+    /// so it lands inside the transformed box of size [`Transform::size`]
+    pub fn apply_in(self, point: Point, width: i32, height: i32) -> Point {
+        let [xx, xy, yx, yy] = self.matrix();
+        let shift = |x_factor: i32, y_factor: i32| {
+            (if x_factor < 0 { width - 1 } else { 0 }) + (if y_factor < 0 { height - 1 } else { 0 })
+        };
+        self.apply(point) + Point::new(shift(xx, xy), shift(yx, yy))
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn direction(self, direction: Direction) -> Direction {
+        Direction::from(self.apply(Point::from(direction)))
+    }
+
+    /// Transform a horizontal direction
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the direction stops being horizontal, e.g. after a rotation by 90 degrees
+    pub fn two_dim_direction(
+        self,
+        direction: TwoDimDirection,
+    ) -> Result<TwoDimDirection, ConvertError> {
+        TwoDimDirection::try_from(self.direction(Direction::from(direction)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConvertError, Direction, Point, Transform, TwoDimDirection};
+    use crate::{Grid, DIR8};
+
+    #[test]
+    fn composition_and_inverse() {
+        for a in Transform::ALL {
+            assert_eq!(Transform::Identity, a.then(a.inverse()));
+            for b in Transform::ALL {
+                let p = Point::new(3, -7);
+                assert_eq!(b.apply(a.apply(p)), a.then(b).apply(p));
+            }
+        }
+        assert_eq!(
+            Transform::Rotate180,
+            Transform::Rotate90.then(Transform::Rotate90)
+        );
+        assert_eq!(
+            Transform::Transpose,
+            Transform::FlipHorizontal.then(Transform::Rotate270)
+        );
+        assert_eq!(Transform::Rotate270, Transform::rotation(-1));
+    }
+
+    #[test]
+    fn points_and_directions() {
+        let origin = Point::new(10, 10);
+        assert_eq!(
+            Point::new(9, 12),
+            Transform::Rotate90.apply_around(Point::new(12, 11), origin)
+        );
+        assert_eq!(
+            Direction::East,
+            Transform::Rotate90.direction(Direction::North)
+        );
+        assert_eq!(
+            Direction::NorthEast,
+            Transform::Rotate90.direction(Direction::NorthWest)
+        );
+        for transform in Transform::ALL {
+            assert_eq!(Direction::Here, transform.direction(Direction::Here));
+            let mut turned = DIR8.map(|dir| transform.direction(dir));
+            turned.sort_by_key(|dir| (dir.dx(), dir.dy()));
+            let mut all = DIR8;
+            all.sort_by_key(|dir| (dir.dx(), dir.dy()));
+            assert_eq!(all, turned);
+        }
+        assert_eq!(
+            Ok(TwoDimDirection::West),
+            Transform::FlipHorizontal.two_dim_direction(TwoDimDirection::East)
+        );
+        assert_eq!(
+            Err(ConvertError::South),
+            Transform::Rotate90.two_dim_direction(TwoDimDirection::East)
+        );
+    }
+
+    #[test]
+    fn boxes_and_grids() {
+        assert_eq!(
+            Point::new(1, 0),
+            Transform::Rotate90.apply_in(Point::new(0, 0), 3, 2)
+        );
+        let grid = Grid::from_fn(3, 2, |p| p.x + p.y * 10);
+        let rotated = grid.transformed(Transform::Rotate90);
+        assert_eq!((2, 3), (rotated.width(), rotated.height()));
+        assert_eq!(rotated.cells(), [10, 0, 11, 1, 12, 2]);
+        let flipped = grid.transformed(Transform::FlipVertical);
+        assert_eq!(flipped.cells(), [10, 11, 12, 0, 1, 2]);
+        for transform in Transform::ALL {
+            assert_eq!(
+                grid,
+                grid.transformed(transform).transformed(transform.inverse())
+            );
+        }
+    }
+}
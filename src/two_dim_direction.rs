@@ -2,9 +2,10 @@ use std::convert::TryFrom;
 
 use super::Direction;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TwoDimDirection {
+    #[default]
     East,
     West,
 }
@@ -16,12 +17,6 @@ impl TwoDimDirection {
     }
 }
 
-impl Default for TwoDimDirection {
-    fn default() -> Self {
-        Self::East
-    }
-}
-
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ConvertError {
     North,
@@ -47,6 +42,15 @@ impl TryFrom<Direction> for TwoDimDirection {
     }
 }
 
+impl From<TwoDimDirection> for Direction {
+    fn from(value: TwoDimDirection) -> Self {
+        match value {
+            TwoDimDirection::East => Direction::East,
+            TwoDimDirection::West => Direction::West,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
@@ -0,0 +1,216 @@
+use super::{Point, Rect, TileRect, Vec2};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Camera showing a part of the world on screen.
+/// `position` is the world tile shown in the top-left screen cell.
+pub struct Viewport {
+    pub position: Point,
+    /// Size of the view in screen cells (tiles)
+    pub width: i32,
+    pub height: i32,
+    /// Size of one tile in pixels at zoom 1
+    pub tile_size: Vec2,
+    pub zoom: f32,
+}
+
+impl Viewport {
+    #[must_use]
+    /// Viewport of `width` x `height` tiles at world origin, with 1x1 pixel tiles and no zoom
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            position: Point::default(),
+            width,
+            height,
+            tile_size: Vec2::one(),
+            zoom: 1.0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_tile_size(mut self, tile_size: Vec2) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    #[must_use]
+    pub fn with_zoom(mut self, zoom: f32) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    /// World tiles currently visible
+    pub fn world_rect(self) -> TileRect {
+        TileRect::new(self.position.x, self.position.y, self.width, self.height)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Screen cell in the middle of the view
+    pub fn screen_center(self) -> Point {
+        Point::new(self.width / 2, self.height / 2)
+    }
+
+    /// Move the view so the target is in its center
+    pub fn center_on(&mut self, target: Point) {
+        self.position = target - self.screen_center();
+    }
+
+    /// Move the view so it doesn't show anything outside of the map.
+    /// If the map is smaller than the view, it's centered instead.
+    pub fn clamp_to(&mut self, map: TileRect) {
+        self.position = Point::new(
+            clamp_axis(self.position.x, self.width, map.x, map.width),
+            clamp_axis(self.position.y, self.height, map.y, map.height),
+        );
+    }
+
+    /// Center on the target, keeping the view inside the map
+    pub fn follow(&mut self, target: Point, map: TileRect) {
+        self.center_on(target);
+        self.clamp_to(map);
+    }
+
+    #[inline]
+    #[must_use]
+    /// Screen cell showing the world tile, may be outside of the screen
+    pub fn world_to_screen(self, world: Point) -> Point {
+        world - self.position
+    }
+
+    #[inline]
+    #[must_use]
+    /// World tile shown in the screen cell
+    pub fn screen_to_world(self, screen: Point) -> Point {
+        screen + self.position
+    }
+
+    #[inline]
+    #[must_use]
+    /// Size of one tile on screen in pixels, with zoom applied
+    pub fn scaled_tile_size(self) -> Vec2 {
+        self.tile_size * self.zoom
+    }
+
+    #[inline]
+    #[must_use]
+    /// Top-left pixel of the screen cell
+    pub fn tile_to_pixel(self, screen: Point) -> Vec2 {
+        Vec2::from(screen) * self.scaled_tile_size()
+    }
+
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    /// Screen cell under the pixel
+    pub fn pixel_to_tile(self, pixel: Vec2) -> Point {
+        let tile = pixel / self.scaled_tile_size();
+        Point::new(tile.x.floor() as i32, tile.y.floor() as i32)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Top-left pixel of the world tile
+    pub fn world_to_pixel(self, world: Point) -> Vec2 {
+        self.tile_to_pixel(self.world_to_screen(world))
+    }
+
+    #[inline]
+    #[must_use]
+    /// World tile under the pixel, e.g. for mouse picking
+    pub fn pixel_to_world(self, pixel: Vec2) -> Point {
+        self.screen_to_world(self.pixel_to_tile(pixel))
+    }
+
+    #[must_use]
+    /// Pixel rectangle occupied by the world tile
+    pub fn tile_pixel_rect(self, world: Point) -> Rect {
+        let position = self.world_to_pixel(world);
+        let size = self.scaled_tile_size();
+        Rect::new(position.x, position.y, size.x, size.y)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Check if the world tile is on screen
+    pub fn is_visible(self, world: Point) -> bool {
+        self.world_rect().contains(world)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Check if any tile of the world rectangle is on screen
+    pub fn is_rect_visible(self, world: TileRect) -> bool {
+        self.world_rect().intersects(world)
+    }
+
+    #[inline]
+    #[must_use]
+    /// Part of the world rectangle on screen, in screen cells
+    pub fn visible_part(self, world: TileRect) -> Option<TileRect> {
+        self.world_rect()
+            .intersection(world)
+            .map(|rect| rect.translated(-self.position))
+    }
+}
+
+/// Start of a view of `size` tiles along one axis, keeping it inside the map
+fn clamp_axis(start: i32, size: i32, map_start: i32, map_size: i32) -> i32 {
+    if map_size <= size {
+        map_start - (size - map_size) / 2
+    } else {
+        start.clamp(map_start, map_start + map_size - size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Point, TileRect, Vec2, Viewport};
+
+    #[test]
+    fn centering_and_clamping() {
+        let mut view = Viewport::new(10, 6);
+        view.center_on(Point::new(20, 20));
+        assert_eq!(Point::new(15, 17), view.position);
+        assert_eq!(Point::new(5, 3), view.world_to_screen(Point::new(20, 20)));
+
+        let map = TileRect::with_size(40, 30);
+        view.follow(Point::new(1, 29), map);
+        assert_eq!(Point::new(0, 24), view.position);
+
+        view.follow(Point::new(5, 5), TileRect::with_size(6, 30));
+        assert_eq!(-2, view.position.x);
+    }
+
+    #[test]
+    fn pixels() {
+        let mut view = Viewport::new(20, 10)
+            .with_tile_size(Vec2::new(16.0, 24.0))
+            .with_zoom(2.0);
+        view.position = Point::new(100, 50);
+        assert_eq!(
+            Vec2::new(64.0, 48.0),
+            view.world_to_pixel(Point::new(102, 51))
+        );
+        assert_eq!(
+            Point::new(102, 51),
+            view.pixel_to_world(Vec2::new(95.9, 95.9))
+        );
+        assert_eq!(
+            Point::new(99, 49),
+            view.pixel_to_world(Vec2::new(-1.0, -1.0))
+        );
+    }
+
+    #[test]
+    fn visibility() {
+        let view = Viewport::new(10, 10);
+        assert!(view.is_visible(Point::new(9, 0)));
+        assert!(!view.is_visible(Point::new(10, 0)));
+        let room = TileRect::new(-3, 8, 5, 5);
+        assert!(view.is_rect_visible(room));
+        assert_eq!(Some(TileRect::new(0, 8, 2, 2)), view.visible_part(room));
+        assert!(!view.is_rect_visible(TileRect::new(10, 0, 5, 5)));
+    }
+}
@@ -0,0 +1,162 @@
+use super::{Distance, Grid, Point, TileRect, DIR4};
+
+#[derive(Clone, Debug, PartialEq)]
+/// Partition of a rectangle into regions, one region per seed.
+pub struct Voronoi {
+    bounds: TileRect,
+    owners: Grid<usize>,
+    borders: Vec<Vec<Point>>,
+}
+
+impl Voronoi {
+    #[inline]
+    #[must_use]
+    /// Rectangle that was partitioned
+    pub fn bounds(&self) -> TileRect {
+        self.bounds
+    }
+
+    #[inline]
+    #[must_use]
+    /// Number of regions, equal to the number of seeds
+    pub fn len(&self) -> usize {
+        self.borders.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.borders.is_empty()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Index of the seed owning the point, `None` if the point is out of bounds
+    pub fn owner(&self, point: Point) -> Option<usize> {
+        self.owners.get(point - self.bounds.position()).copied()
+    }
+
+    #[inline]
+    #[must_use]
+    /// Owner map relative to the top-left corner of bounds
+    pub fn owners(&self) -> &Grid<usize> {
+        &self.owners
+    }
+
+    #[must_use]
+    /// Points of the region touching another region on a side, empty for unknown region
+    pub fn border(&self, region: usize) -> &[Point] {
+        self.borders.get(region).map_or(&[], Vec::as_slice)
+    }
+
+    /// Iterate over all points belonging to the region
+    pub fn region(&self, region: usize) -> impl Iterator<Item = Point> + '_ {
+        let offset = self.bounds.position();
+        self.owners
+            .iter()
+            .filter(move |(_, &owner)| owner == region)
+            .map(move |(point, _)| point + offset)
+    }
+}
+
+#[must_use]
+/// Assign every tile of `bounds` to its nearest seed under the given metric.
+/// Ties are resolved in favour of the seed with lower index.
+/// Returns `None` if there are no seeds.
+pub fn voronoi(seeds: &[Point], bounds: TileRect, metric: Distance) -> Option<Voronoi> {
+    partition(seeds.len(), bounds, |i, point| {
+        metric.between(seeds[i], point)
+    })
+}
+
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+/// Power (weighted) Voronoi: every tile goes to the site with the lowest
+/// `squared distance - weight`, so heavier sites claim larger regions.
+/// Returns `None` if there are no sites.
+pub fn power_voronoi(sites: &[(Point, f32)], bounds: TileRect) -> Option<Voronoi> {
+    partition(sites.len(), bounds, |i, point| {
+        let (seed, weight) = sites[i];
+        seed.square_distance_to(point) as f32 - weight
+    })
+}
+
+fn partition<F>(count: usize, bounds: TileRect, cost: F) -> Option<Voronoi>
+where
+    F: Fn(usize, Point) -> f32,
+{
+    if count == 0 {
+        return None;
+    }
+
+    let offset = bounds.position();
+    let owners = Grid::from_fn(bounds.width, bounds.height, |local| {
+        let point = local + offset;
+        (1..count).fold(0, |best, i| {
+            if cost(i, point) < cost(best, point) {
+                i
+            } else {
+                best
+            }
+        })
+    });
+
+    let mut borders = vec![Vec::new(); count];
+    for (local, &owner) in owners.iter() {
+        let on_border = DIR4.iter().any(|&dir| {
+            owners
+                .get(local + dir)
+                .is_some_and(|&neighbour| neighbour != owner)
+        });
+        if on_border {
+            borders[owner].push(local + offset);
+        }
+    }
+
+    Some(Voronoi {
+        bounds,
+        owners,
+        borders,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{power_voronoi, voronoi, Distance, Point, TileRect};
+
+    #[test]
+    fn two_seeds() {
+        let seeds = [Point::new(0, 0), Point::new(9, 0)];
+        let map = voronoi(&seeds, TileRect::with_size(10, 3), Distance::Euclidean).unwrap();
+        assert_eq!(2, map.len());
+        assert_eq!(Some(0), map.owner(Point::new(4, 2)));
+        assert_eq!(Some(1), map.owner(Point::new(5, 2)));
+        assert_eq!(None, map.owner(Point::new(10, 0)));
+        assert_eq!(15, map.region(0).count());
+        assert_eq!(map.border(0), [(4, 0), (4, 1), (4, 2)]);
+        assert_eq!(map.border(1), [(5, 0), (5, 1), (5, 2)]);
+    }
+
+    #[test]
+    fn offset_bounds() {
+        let seeds = [Point::new(-5, -5), Point::new(5, 5)];
+        let bounds = TileRect::new(-5, -5, 11, 11);
+        let map = voronoi(&seeds, bounds, Distance::Chebyshev).unwrap();
+        assert_eq!(Some(0), map.owner(Point::new(-5, 5)));
+        assert_eq!(Some(1), map.owner(Point::new(1, 1)));
+        assert!(map.region(1).all(|p| bounds.contains(p)));
+    }
+
+    #[test]
+    fn weighted() {
+        let sites = [(Point::new(0, 0), 0.0), (Point::new(9, 0), 40.0)];
+        let map = power_voronoi(&sites, TileRect::with_size(10, 1)).unwrap();
+        assert_eq!(Some(1), map.owner(Point::new(3, 0)));
+        assert_eq!(Some(0), map.owner(Point::new(2, 0)));
+    }
+
+    #[test]
+    fn no_seeds() {
+        assert!(voronoi(&[], TileRect::with_size(5, 5), Distance::Manhattan).is_none());
+    }
+}
@@ -0,0 +1,637 @@
+//! Wave Function Collapse: fill a grid with tiles so that every pair of neighbours
+//! satisfies adjacency rules. Rules are either written by hand (`TiledModel`)
+//! or learned from an example grid (`OverlappingModel`).
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{Direction, Grid, Point, TileRect, DIR4};
+
+/// Default number of undone choices before giving up
+const DEFAULT_BACKTRACK_LIMIT: usize = 1000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WfcError {
+    /// Model has no tiles or patterns to place
+    Empty,
+    /// Constraints can't be satisfied within the backtrack limit
+    Contradiction,
+    /// Tile weight is negative, infinite or NaN
+    InvalidWeight,
+    /// No tile was added with this index
+    UnknownTile(usize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Which transformed copies of sample patterns are also learned
+pub enum Symmetry {
+    /// Patterns are used as they are in the sample
+    #[default]
+    Identity,
+    /// Patterns are also rotated by 90, 180 and 270 degrees
+    Rotate,
+    /// Patterns are rotated and mirrored, giving up to eight variants
+    RotateReflect,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Weighted tiles identified by index and which of them may be placed next to each other.
+pub struct Rules {
+    weights: Vec<f32>,
+    /// `allowed[side][a][b]` is true if tile `b` may be placed at `side` of tile `a`
+    allowed: [Vec<Vec<bool>>; 4],
+}
+
+impl Rules {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a tile with relative frequency `weight`, returning its index
+    ///
+    /// # Errors
+    ///
+    /// Returns `WfcError::InvalidWeight` if the weight is negative or not finite.
+    pub fn add_tile(&mut self, weight: f32) -> Result<usize, WfcError> {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(WfcError::InvalidWeight);
+        }
+        Ok(self.push_tile(weight))
+    }
+
+    fn push_tile(&mut self, weight: f32) -> usize {
+        let index = self.weights.len();
+        self.weights.push(weight);
+        for side in &mut self.allowed {
+            for row in side.iter_mut() {
+                row.push(false);
+            }
+            side.push(vec![false; index + 1]);
+        }
+        index
+    }
+
+    #[inline]
+    #[must_use]
+    /// Number of tiles
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// Allow tile `to` at `direction` of tile `from`, and `from` at the opposite side of `to`.
+    /// Only cardinal directions are meaningful, other directions are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WfcError::UnknownTile` if either tile wasn't added.
+    pub fn allow(&mut self, from: usize, direction: Direction, to: usize) -> Result<(), WfcError> {
+        for tile in [from, to] {
+            if tile >= self.len() {
+                return Err(WfcError::UnknownTile(tile));
+            }
+        }
+        self.permit(from, direction, to);
+        Ok(())
+    }
+
+    fn permit(&mut self, from: usize, direction: Direction, to: usize) {
+        if let Some(side) = side_index(direction) {
+            self.allowed[side][from][to] = true;
+            self.allowed[(side + 2) % 4][to][from] = true;
+        }
+    }
+
+    #[must_use]
+    /// Check if tile `to` may be placed at `direction` of tile `from`
+    pub fn is_allowed(&self, from: usize, direction: Direction, to: usize) -> bool {
+        side_index(direction).is_some_and(|side| {
+            self.allowed[side]
+                .get(from)
+                .and_then(|row| row.get(to))
+                .copied()
+                .unwrap_or(false)
+        })
+    }
+
+    /// Fill a grid with tile indices satisfying all rules.
+    /// When a choice leads to a contradiction it is undone and another tile is tried,
+    /// up to `backtrack_limit` times in total.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WfcError::Empty` if there are no tiles
+    /// and `WfcError::Contradiction` if no solution was found.
+    pub fn solve<R: rand::Rng + ?Sized>(
+        &self,
+        width: i32,
+        height: i32,
+        backtrack_limit: usize,
+        rng: &mut R,
+    ) -> Result<Grid<usize>, WfcError> {
+        if self.is_empty() {
+            return Err(WfcError::Empty);
+        }
+        Solver::new(self, width, height).run(backtrack_limit, rng)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Model with explicitly listed tiles and adjacency rules.
+pub struct TiledModel<T> {
+    tiles: Vec<T>,
+    rules: Rules,
+    pub backtrack_limit: usize,
+}
+
+impl<T: Clone> TiledModel<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            tiles: Vec::new(),
+            rules: Rules::new(),
+            backtrack_limit: DEFAULT_BACKTRACK_LIMIT,
+        }
+    }
+
+    /// Add a tile with relative frequency `weight`, returning its index
+    ///
+    /// # Errors
+    ///
+    /// See [`Rules::add_tile`].
+    pub fn add_tile(&mut self, tile: T, weight: f32) -> Result<usize, WfcError> {
+        let index = self.rules.add_tile(weight)?;
+        self.tiles.push(tile);
+        Ok(index)
+    }
+
+    /// Allow tile `to` at `direction` of tile `from` (and vice versa)
+    ///
+    /// # Errors
+    ///
+    /// See [`Rules::allow`].
+    pub fn allow(&mut self, from: usize, direction: Direction, to: usize) -> Result<(), WfcError> {
+        self.rules.allow(from, direction, to)
+    }
+
+    #[must_use]
+    pub fn tiles(&self) -> &[T] {
+        &self.tiles
+    }
+
+    #[must_use]
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Generate a grid of tiles
+    ///
+    /// # Errors
+    ///
+    /// See [`Rules::solve`].
+    pub fn generate<R: rand::Rng + ?Sized>(
+        &self,
+        width: i32,
+        height: i32,
+        rng: &mut R,
+    ) -> Result<Grid<T>, WfcError> {
+        self.rules
+            .solve(width, height, self.backtrack_limit, rng)
+            .map(|grid| grid.map(|_, &tile| self.tiles[tile].clone()))
+    }
+}
+
+impl<T: Clone> Default for TiledModel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Model learning `size` x `size` patterns from an example grid.
+/// Neighbouring cells of the output always hold patterns that overlap consistently.
+pub struct OverlappingModel<T> {
+    size: i32,
+    patterns: Vec<Vec<T>>,
+    rules: Rules,
+    pub backtrack_limit: usize,
+}
+
+impl<T: Clone + Eq + Hash> OverlappingModel<T> {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    /// Learn all `size` x `size` patterns of the sample, weighted by how often they occur
+    pub fn new(sample: &Grid<T>, size: i32, symmetry: Symmetry) -> Self {
+        let size = size.max(1);
+        let mut patterns: Vec<Vec<T>> = Vec::new();
+        let mut counts: Vec<usize> = Vec::new();
+        let mut known: HashMap<Vec<T>, usize> = HashMap::new();
+
+        for y in 0..=sample.height() - size {
+            for x in 0..=sample.width() - size {
+                let pattern: Vec<T> = (0..size)
+                    .flat_map(|dy| (0..size).map(move |dx| Point::new(x + dx, y + dy)))
+                    .map(|point| sample[point].clone())
+                    .collect();
+                for variant in variants(pattern, size, symmetry) {
+                    if let Some(&index) = known.get(&variant) {
+                        counts[index] += 1;
+                    } else {
+                        known.insert(variant.clone(), patterns.len());
+                        patterns.push(variant);
+                        counts.push(1);
+                    }
+                }
+            }
+        }
+
+        let mut rules = Rules::new();
+        for &count in &counts {
+            rules.push_tile(count as f32);
+        }
+        for (a, first) in patterns.iter().enumerate() {
+            for (b, second) in patterns.iter().enumerate() {
+                for direction in DIR4 {
+                    if overlaps(first, second, size, direction) {
+                        rules.permit(a, direction, b);
+                    }
+                }
+            }
+        }
+
+        Self {
+            size,
+            patterns,
+            rules,
+            backtrack_limit: DEFAULT_BACKTRACK_LIMIT,
+        }
+    }
+
+    #[must_use]
+    /// Width and height of learned patterns
+    pub fn pattern_size(&self) -> i32 {
+        self.size
+    }
+
+    #[must_use]
+    /// Number of distinct patterns learned
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    #[must_use]
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Generate a grid of tiles, each cell gets the top-left tile of its pattern
+    ///
+    /// # Errors
+    ///
+    /// See [`Rules::solve`].
+    pub fn generate<R: rand::Rng + ?Sized>(
+        &self,
+        width: i32,
+        height: i32,
+        rng: &mut R,
+    ) -> Result<Grid<T>, WfcError> {
+        self.rules
+            .solve(width, height, self.backtrack_limit, rng)
+            .map(|grid| grid.map(|_, &pattern| self.patterns[pattern][0].clone()))
+    }
+}
+
+/// Check if `second` placed one step in `direction` from `first` agrees on the overlap
+fn overlaps<T: Eq>(first: &[T], second: &[T], size: i32, direction: Direction) -> bool {
+    let (dx, dy) = (direction.dx(), direction.dy());
+    let at = |x: i32, y: i32| Point::new(x, y).to_index(size).unwrap_or(0);
+    (dy.max(0)..size + dy.min(0)).all(|y| {
+        (dx.max(0)..size + dx.min(0)).all(|x| first[at(x, y)] == second[at(x - dx, y - dy)])
+    })
+}
+
+/// Index of a side in `DIR4`, opposite sides are two steps apart
+fn side_index(direction: Direction) -> Option<usize> {
+    DIR4.iter().position(|&side| side == direction)
+}
+
+/// Pattern itself and its transformed copies according to symmetry
+fn variants<T: Clone>(pattern: Vec<T>, size: i32, symmetry: Symmetry) -> Vec<Vec<T>> {
+    let mut result = vec![pattern];
+    if symmetry == Symmetry::Identity {
+        return result;
+    }
+    for _ in 0..3 {
+        let rotated = remap(&result[result.len() - 1], size, |x, y| (y, size - 1 - x));
+        result.push(rotated);
+    }
+    if symmetry == Symmetry::RotateReflect {
+        for i in 0..4 {
+            let reflected = remap(&result[i], size, |x, y| (size - 1 - x, y));
+            result.push(reflected);
+        }
+    }
+    result
+}
+
+/// Build a new pattern taking tile `(x, y)` from `source(x, y)` of the old one
+fn remap<T: Clone, F>(pattern: &[T], size: i32, source: F) -> Vec<T>
+where
+    F: Fn(i32, i32) -> (i32, i32),
+{
+    (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (sx, sy) = source(x, y);
+            pattern[Point::new(sx, sy).to_index(size).unwrap_or(0)].clone()
+        })
+        .collect()
+}
+
+struct Solver<'a> {
+    rules: &'a Rules,
+    width: i32,
+    height: i32,
+    /// `possible[cell * tiles + tile]`
+    possible: Vec<bool>,
+    remaining: Vec<usize>,
+    /// Removed `(cell, tile)` pairs in order, to undo them when backtracking
+    trail: Vec<(usize, usize)>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(rules: &'a Rules, width: i32, height: i32) -> Self {
+        let (width, height) = (width.max(0), height.max(0));
+        let cells = TileRect::with_size(width, height).area();
+        Self {
+            rules,
+            width,
+            height,
+            possible: vec![true; cells * rules.len()],
+            remaining: vec![rules.len(); cells],
+            trail: Vec::new(),
+        }
+    }
+
+    fn run<R: rand::Rng + ?Sized>(
+        mut self,
+        backtrack_limit: usize,
+        rng: &mut R,
+    ) -> Result<Grid<usize>, WfcError> {
+        let all_cells: Vec<usize> = (0..self.remaining.len()).collect();
+        if !self.propagate(all_cells) {
+            return Err(WfcError::Contradiction);
+        }
+        self.trail.clear();
+
+        // Every choice remembers where the trail was before it was made
+        let mut choices: Vec<(usize, usize, usize)> = Vec::new();
+        let mut backtracks = 0;
+        while let Some(cell) = self.lowest_entropy_cell(rng) {
+            let tile = self.pick_tile(cell, rng);
+            choices.push((self.trail.len(), cell, tile));
+            let mut consistent = self.collapse(cell, tile);
+
+            while !consistent {
+                let Some((trail_len, cell, tile)) = choices.pop() else {
+                    return Err(WfcError::Contradiction);
+                };
+                backtracks += 1;
+                if backtracks > backtrack_limit {
+                    return Err(WfcError::Contradiction);
+                }
+                self.undo(trail_len);
+                consistent = self.remove(cell, tile) && self.propagate(vec![cell]);
+            }
+        }
+
+        let tiles = self.rules.len();
+        let possible = &self.possible;
+        Ok(Grid::from_fn(self.width, self.height, |point| {
+            let cell = point.to_index(self.width).unwrap_or(0);
+            (0..tiles)
+                .find(|&tile| possible[cell * tiles + tile])
+                .unwrap_or(0)
+        }))
+    }
+
+    /// Undecided cell with the fewest options, ties are broken randomly
+    fn lowest_entropy_cell<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let mut best = None;
+        let mut best_count = usize::MAX;
+        let mut ties = 0;
+        for (cell, &count) in self.remaining.iter().enumerate() {
+            if count <= 1 || count > best_count {
+                continue;
+            }
+            if count < best_count {
+                best_count = count;
+                ties = 0;
+            }
+            ties += 1;
+            if rng.random_range(0..ties) == 0 {
+                best = Some(cell);
+            }
+        }
+        best
+    }
+
+    fn pick_tile<R: rand::Rng + ?Sized>(&self, cell: usize, rng: &mut R) -> usize {
+        let options = self.options(cell);
+        let total: f32 = options.iter().map(|&tile| self.rules.weights[tile]).sum();
+        let mut roll = rng.random::<f32>() * total;
+        for &tile in &options {
+            roll -= self.rules.weights[tile];
+            if roll < 0.0 {
+                return tile;
+            }
+        }
+        options[options.len() - 1]
+    }
+
+    fn options(&self, cell: usize) -> Vec<usize> {
+        let tiles = self.rules.len();
+        (0..tiles)
+            .filter(|&tile| self.possible[cell * tiles + tile])
+            .collect()
+    }
+
+    fn collapse(&mut self, cell: usize, tile: usize) -> bool {
+        for other in self.options(cell) {
+            if other != tile {
+                self.remove(cell, other);
+            }
+        }
+        self.propagate(vec![cell])
+    }
+
+    /// Forbid tile in the cell, returns false if the cell has no options left
+    fn remove(&mut self, cell: usize, tile: usize) -> bool {
+        let index = cell * self.rules.len() + tile;
+        if self.possible[index] {
+            self.possible[index] = false;
+            self.remaining[cell] -= 1;
+            self.trail.push((cell, tile));
+        }
+        self.remaining[cell] > 0
+    }
+
+    fn undo(&mut self, trail_len: usize) {
+        let tiles = self.rules.len();
+        for (cell, tile) in self.trail.drain(trail_len..) {
+            self.possible[cell * tiles + tile] = true;
+            self.remaining[cell] += 1;
+        }
+    }
+
+    /// Remove options of neighbours that no longer have support, returns false on contradiction
+    fn propagate(&mut self, mut pending: Vec<usize>) -> bool {
+        let tiles = self.rules.len();
+        while let Some(cell) = pending.pop() {
+            let point = Point::from_index(cell, self.width);
+            let options = self.options(cell);
+            for (side, &direction) in DIR4.iter().enumerate() {
+                let neighbour = point + direction;
+                if neighbour.x < 0
+                    || neighbour.y < 0
+                    || neighbour.x >= self.width
+                    || neighbour.y >= self.height
+                {
+                    continue;
+                }
+                let Some(neighbour) = neighbour.to_index(self.width) else {
+                    continue;
+                };
+                let mut changed = false;
+                for other in 0..tiles {
+                    if !self.possible[neighbour * tiles + other] {
+                        continue;
+                    }
+                    let supported = options
+                        .iter()
+                        .any(|&tile| self.rules.allowed[side][tile][other]);
+                    if !supported {
+                        if !self.remove(neighbour, other) {
+                            return false;
+                        }
+                        changed = true;
+                    }
+                }
+                if changed {
+                    pending.push(neighbour);
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::{Direction, Grid, OverlappingModel, Point, Symmetry, TiledModel, WfcError};
+
+    fn checkers() -> TiledModel<char> {
+        let mut model = TiledModel::new();
+        let black = model.add_tile('#', 1.0).unwrap();
+        let white = model.add_tile('.', 1.0).unwrap();
+        for direction in [Direction::East, Direction::South] {
+            model.allow(black, direction, white).unwrap();
+            model.allow(white, direction, black).unwrap();
+        }
+        model
+    }
+
+    #[test]
+    fn tiled_checkers() {
+        let grid = checkers()
+            .generate(8, 6, &mut StdRng::seed_from_u64(1))
+            .unwrap();
+        for (point, tile) in grid.iter() {
+            for direction in [Direction::East, Direction::South] {
+                if let Some(neighbour) = grid.get(point + direction) {
+                    assert_ne!(tile, neighbour);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn seeded_determinism() {
+        let mut model = TiledModel::new();
+        let tiles: Vec<usize> = (0..4).map(|i| model.add_tile(i, 1.0).unwrap()).collect();
+        for &a in &tiles {
+            for &b in &tiles {
+                if a.abs_diff(b) <= 1 {
+                    model.allow(a, Direction::East, b).unwrap();
+                    model.allow(a, Direction::South, b).unwrap();
+                }
+            }
+        }
+        let first = model.generate(10, 10, &mut StdRng::seed_from_u64(7));
+        let second = model.generate(10, 10, &mut StdRng::seed_from_u64(7));
+        assert!(first.is_ok());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn contradiction() {
+        let mut model = TiledModel::new();
+        let lonely = model.add_tile('x', 1.0).unwrap();
+        model.allow(lonely, Direction::East, lonely).unwrap();
+        assert_eq!(
+            Err(WfcError::UnknownTile(1)),
+            model.allow(lonely, Direction::East, 1)
+        );
+        assert_eq!(Err(WfcError::InvalidWeight), model.add_tile('y', -1.0));
+        assert_eq!(Err(WfcError::InvalidWeight), model.add_tile('y', f32::NAN));
+        assert_eq!(1, model.tiles().len());
+        let result = model.generate(3, 3, &mut StdRng::seed_from_u64(0));
+        assert_eq!(Err(WfcError::Contradiction), result);
+        let empty: TiledModel<char> = TiledModel::new();
+        let result = empty.generate(3, 3, &mut StdRng::seed_from_u64(0));
+        assert_eq!(Err(WfcError::Empty), result);
+    }
+
+    #[test]
+    fn overlapping_stripes() {
+        let sample = Grid::from_fn(6, 6, |p| if p.x % 3 == 0 { '|' } else { ' ' });
+        let model = OverlappingModel::new(&sample, 3, Symmetry::Identity);
+        assert_eq!(3, model.pattern_count());
+        let grid = model
+            .generate(12, 5, &mut StdRng::seed_from_u64(3))
+            .unwrap();
+        for (point, &tile) in grid.iter() {
+            assert_eq!(Some(&tile), grid.get(Point::new(point.x, 0)));
+            if let Some(&next) = grid.get(point + (3, 0)) {
+                assert_eq!(tile, next);
+            }
+        }
+    }
+
+    #[test]
+    fn symmetry_variants() {
+        let sample = Grid::from_fn(2, 2, |p| p == Point::new(0, 0));
+        assert_eq!(
+            1,
+            OverlappingModel::new(&sample, 2, Symmetry::Identity).pattern_count()
+        );
+        assert_eq!(
+            4,
+            OverlappingModel::new(&sample, 2, Symmetry::Rotate).pattern_count()
+        );
+        assert_eq!(
+            4,
+            OverlappingModel::new(&sample, 2, Symmetry::RotateReflect).pattern_count()
+        );
+    }
+}